@@ -0,0 +1,168 @@
+//! # Builtin Macros
+//! The macros defined by the compiler itself.
+//!
+//! Most of them are plain object-like macros defined before
+//! the first line of the source, but a few of them, like
+//! `__LINE__`, expand to something different every time.
+//!
+//! `__DATE__` and `__TIME__` are the time the preprocessor
+//! started, unless the `SOURCE_DATE_EPOCH` environment
+//! variable is set (see <https://reproducible-builds.org/specs/source-date-epoch/>),
+//! in which case that UTC timestamp is used instead, so that
+//! builds are reproducible.
+
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::rc::Rc;
+
+use chrono::{Local, NaiveDateTime};
+
+use crate::util::intern::Symbol;
+use crate::util::lang::Standard;
+use crate::util::scan::location::Location;
+
+use super::macros::{Macro, MacroKind};
+use super::token::PPToken;
+use super::Preprocessor;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// # Builtin
+/// The builtin macros whose expansion depends on where
+/// they are expanded.
+pub enum Builtin {
+    /// `__FILE__`, the name of the current file
+    File,
+    /// `__LINE__`, the current line
+    Line,
+    /// `__COUNTER__`, starts at 0 and is incremented at each expansion
+    Counter,
+}
+
+/// Returns `__DATE__` and `__TIME__`.
+fn timestamp() -> (String, String) {
+    let epoch = env::var("SOURCE_DATE_EPOCH").ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .and_then(|epoch| NaiveDateTime::from_timestamp_opt(epoch, 0));
+    let now = epoch.unwrap_or_else(|| Local::now().naive_local());
+    // The day is padded with a space, not a zero: "Jan  1 1970"
+    return (now.format("%b %e %Y").to_string(), now.format("%H:%M:%S").to_string());
+}
+
+/// # Target Macros
+/// The macros describing the target. Tornado only targets
+/// the machine it runs on, for now.
+fn target_macros() -> Vec<(&'static str, &'static str)> {
+    let mut macros = vec![("__CHAR_BIT__", "8"), ("__SIZEOF_SHORT__", "2"), ("__SIZEOF_INT__", "4"),
+        ("__SIZEOF_LONG_LONG__", "8"), ("__SIZEOF_FLOAT__", "4"), ("__SIZEOF_DOUBLE__", "8"),
+        ("__ORDER_LITTLE_ENDIAN__", "1234"), ("__ORDER_BIG_ENDIAN__", "4321")];
+
+    if cfg!(target_pointer_width = "64") {
+        macros.extend([("__SIZEOF_POINTER__", "8"), ("__SIZEOF_SIZE_T__", "8")]);
+        // Windows is LLP64, everyone else is LP64
+        if !cfg!(windows) {
+            macros.extend([("__LP64__", "1"), ("_LP64", "1"), ("__SIZEOF_LONG__", "8")]);
+        } else {
+            macros.push(("__SIZEOF_LONG__", "4"));
+        }
+    } else {
+        macros.extend([("__SIZEOF_POINTER__", "4"), ("__SIZEOF_SIZE_T__", "4"), ("__SIZEOF_LONG__", "4"),
+            ("__ILP32__", "1"), ("_ILP32", "1")]);
+    }
+    if cfg!(target_endian = "little") {
+        macros.push(("__BYTE_ORDER__", "1234"));
+    } else {
+        macros.push(("__BYTE_ORDER__", "4321"));
+    }
+
+    if cfg!(target_arch = "x86_64") {
+        macros.extend([("__x86_64__", "1"), ("__x86_64", "1"), ("__amd64__", "1"), ("__amd64", "1")]);
+    } else if cfg!(target_arch = "x86") {
+        macros.extend([("__i386__", "1"), ("__i386", "1"), ("i386", "1")]);
+    } else if cfg!(target_arch = "aarch64") {
+        macros.push(("__aarch64__", "1"));
+    } else if cfg!(target_arch = "riscv64") {
+        macros.extend([("__riscv", "1"), ("__riscv_xlen", "64")]);
+    }
+
+    if cfg!(target_os = "linux") {
+        macros.extend([("__linux__", "1"), ("__linux", "1"), ("__gnu_linux__", "1"),
+            ("__unix__", "1"), ("__unix", "1"), ("__ELF__", "1")]);
+    } else if cfg!(target_os = "macos") {
+        macros.extend([("__APPLE__", "1"), ("__MACH__", "1")]);
+    } else if cfg!(target_os = "freebsd") {
+        macros.extend([("__FreeBSD__", "1"), ("__unix__", "1"), ("__unix", "1"), ("__ELF__", "1")]);
+    } else if cfg!(windows) {
+        macros.extend([("_WIN32", "1"), ("_WIN64", "1")]);
+    }
+    return macros;
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Define Builtins
+    /// Defines every builtin and predefined macro. Redefining or
+    /// undefining the builtin ones triggers a warning, while the
+    /// ones describing the target can be changed freely.
+    pub(super) fn define_builtins(&mut self) {
+        let location = Location::new();
+        let (date, time) = timestamp();
+
+        let mut builtins = vec![
+            ("__STDC__", PPToken::number("1", location)),
+            ("__STDC_HOSTED__", PPToken::number("1", location)),
+            ("__STDC_UTF_16__", PPToken::number("1", location)),
+            ("__STDC_UTF_32__", PPToken::number("1", location)),
+            ("__DATE__", PPToken::string(&date, location)),
+            ("__TIME__", PPToken::string(&time, location)),
+            // The values of `__has_embed`
+            ("__STDC_EMBED_NOT_FOUND__", PPToken::number("0", location)),
+            ("__STDC_EMBED_FOUND__", PPToken::number("1", location)),
+            ("__STDC_EMBED_EMPTY__", PPToken::number("2", location)),
+        ];
+        if let Some(version) = self.standard.version() {
+            builtins.push(("__STDC_VERSION__", PPToken::number(version, location)));
+        }
+        // Optional features of C11 that are not supported (yet)
+        if self.standard >= Standard::C11 {
+            for feature in ["__STDC_NO_ATOMICS__", "__STDC_NO_COMPLEX__", "__STDC_NO_THREADS__", "__STDC_NO_VLA__"] {
+                builtins.push((feature, PPToken::number("1", location)));
+            }
+        }
+
+        for (name, tok) in builtins {
+            self.define_predefined(name, MacroKind::Object, vec![tok], true);
+        }
+        for (name, builtin) in [("__FILE__", Builtin::File), ("__LINE__", Builtin::Line), ("__COUNTER__", Builtin::Counter)] {
+            self.define_predefined(name, MacroKind::Builtin(builtin), vec![], true);
+        }
+        for (name, value) in target_macros() {
+            self.define_predefined(name, MacroKind::Object, vec![PPToken::number(value, location)], false);
+        }
+    }
+
+    fn define_predefined(&mut self, name: &str, kind: MacroKind, body: Vec<PPToken>, builtin: bool) {
+        let name = Symbol::intern(name);
+        let mac = Macro { name, kind, body, location: Location::new(), origin: None, builtin };
+        self.macros.insert(name, Rc::new(mac));
+    }
+
+    /// # Expand Builtin
+    /// Returns the expansion of a builtin macro invoked by `tok`.
+    pub(super) fn expand_builtin(&mut self, builtin: Builtin, tok: &PPToken) -> PPToken {
+        match builtin {
+            Builtin::File => {
+                let file = self.includes.last().unwrap().origin.file.to_string_lossy().into_owned();
+                return PPToken::string(&file, tok.location);
+            },
+            // Lines are counted from 0
+            Builtin::Line => {
+                let line = self.presumed_line(tok.location) + 1;
+                return PPToken::number(&line.to_string(), tok.location);
+            },
+            Builtin::Counter => {
+                self.counter += 1;
+                return PPToken::number(&(self.counter - 1).to_string(), tok.location);
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! # Command Line
+//! The macros and files given to the driver, which are
+//! processed after the builtin macros and before the first
+//! line of the source, in GCC's order:
+//!
+//! 1. `-DNAME`, `-DNAME=value` and `-UNAME`, in the order given
+//! 2. `-imacros file`: only the macros of `file` are kept
+//! 3. `-include file`: as if `#include "file"` was the first line
+//!
+//! They are turned into directives, read from a `<command-line>`
+//! file, so they behave exactly like their `#define`, `#undef`
+//! and `#include` counterparts (undefining a builtin macro
+//! with `-U` warns, for example).
+
+#![allow(clippy::needless_return)]
+
+use std::mem;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::front::lex::Lexer;
+use crate::util::diag::Origin;
+use crate::util::scan::location::Location;
+
+use super::include::Include;
+use super::print::Linemarker;
+use super::token;
+use super::Preprocessor;
+
+/// The name of the file holding the command line definitions.
+const COMMAND_LINE: &str = "<command-line>";
+
+#[derive(Debug, Default)]
+/// # Command Line
+/// What the driver asked for, still to be processed.
+pub(super) struct CommandLine {
+    /// ## Definitions
+    /// `#define` and `#undef` directives, one per line.
+    definitions: String,
+    imacros: Vec<String>,
+    includes: Vec<String>,
+}
+
+impl CommandLine {
+    fn is_empty(&self) -> bool {
+        return self.definitions.is_empty() && self.imacros.is_empty() && self.includes.is_empty();
+    }
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Define Macro
+    /// Defines a macro like `-D` does: `NAME` defines `NAME` as `1`,
+    /// `NAME=value` as `value`, and `NAME(x)=value` is a function-like
+    /// macro.
+    pub fn define_macro(&mut self, definition: &str) {
+        // Like GCC, only the first line is used
+        let definition = definition.lines().next().unwrap_or("");
+        let line = match definition.split_once('=') {
+            Some((name, value)) => format!("#define {name} {value}\n"),
+            None => format!("#define {definition} 1\n")
+        };
+        self.command_line.definitions.push_str(&line);
+    }
+
+    /// # Undefine Macro
+    /// Undefines a macro like `-U` does.
+    pub fn undefine_macro(&mut self, name: &str) {
+        self.command_line.definitions.push_str(&format!("#undef {name}\n"));
+    }
+
+    /// # Include File
+    /// Includes `file` before the first line of the source, like
+    /// `-include` does.
+    pub fn include_file(&mut self, file: &str) {
+        self.command_line.includes.push(file.to_owned());
+    }
+
+    /// # Include Macros
+    /// Like `include_file()`, but only keeps the macros defined by
+    /// `file` and throws away the rest, like `-imacros` does.
+    pub fn include_macros(&mut self, file: &str) {
+        self.command_line.imacros.push(file.to_owned());
+    }
+
+    /// # Process Command Line
+    /// Processes the definitions and files given by the driver,
+    /// as if they were in a file included before the source.
+    pub(super) fn process_command_line(&mut self) {
+        let command_line = mem::take(&mut self.command_line);
+        if command_line.is_empty() {
+            return;
+        }
+        let origin = Rc::new(Origin { file: COMMAND_LINE.into(), included_from: None, delta: 0 });
+        self.includes.push(Include {
+            path: PathBuf::from(COMMAND_LINE),
+            origin: origin.clone(),
+            location: Location::new(),
+        });
+        self.linemarkers.push(Linemarker { index: 0, origin, line: 0, physical: 0, flag: None });
+
+        self.run(&command_line.definitions);
+        for file in command_line.imacros.iter() {
+            let (output, linemarkers, pragmas) = (self.output.len(), self.linemarkers.len(), self.pragmas.len());
+            let unparsable = self.unparsable.len();
+            self.run(&format!("#include \"{file}\"\n"));
+            self.output.truncate(output);
+            self.linemarkers.truncate(linemarkers);
+            self.pragmas.truncate(pragmas);
+            self.unparsable.truncate(unparsable);
+        }
+        for file in command_line.includes.iter() {
+            self.run(&format!("#include \"{file}\"\n"));
+        }
+
+        // Back to the source
+        self.includes.pop();
+        let origin = self.includes[0].origin.clone();
+        self.linemarkers.push(Linemarker { index: self.output.len(), origin, line: 0, physical: 0, flag: None });
+    }
+
+    /// # Run
+    /// Preprocesses `source` as part of the `<command-line>` file.
+    fn run(&mut self, source: &str) {
+        let file = self.sources.add(COMMAND_LINE, source.as_bytes());
+        let mut lexer = Lexer::new(source.as_bytes(), file, self.diag);
+        lexer.set_trigraphs(self.trigraphs);
+        lexer.set_standard(self.standard);
+        lexer.set_extensions(self.extensions);
+        let mut tokens = token::from_tokens(source.as_bytes(), lexer, self.trigraphs);
+        // Without the EOF the preprocessor stops at the end of the
+        // tokens, instead of leaving the `<command-line>` file
+        tokens.pop();
+        let saved = mem::replace(&mut self.input, tokens.into_iter().rev().collect());
+        self.init();
+        self.unterminated_conditionals();
+        self.input = saved;
+    }
+}
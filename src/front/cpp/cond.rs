@@ -0,0 +1,177 @@
+//! # Conditional Compilation
+//! `#if`, `#ifdef`, `#ifndef`, `#elif`, `#elifdef`, `#elifndef`,
+//! `#else` and `#endif`.
+//!
+//! Every conditional that is opened is pushed onto a stack
+//! together with its state. When a group has to be skipped
+//! the tokens are thrown away until the next `#elif`, `#else`
+//! or `#endif` of the same nesting level, which is then
+//! executed as a normal directive.
+
+#![allow(clippy::needless_return)]
+
+use crate::front::lex::TokenType;
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::scan::location::Location;
+
+use super::Preprocessor;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum CondState {
+    /// The current group is being included
+    Taking,
+    /// No group has been included yet
+    Skipping,
+    /// A group has already been included, so all
+    /// the remaining ones are skipped
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Conditional {
+    pub state: CondState,
+    /// Location of the `#if`, for unterminated conditionals
+    pub location: Location,
+    pub seen_else: bool,
+    /// Depth of the include stack the conditional was opened
+    /// at, since conditionals cannot span multiple files.
+    pub depth: usize,
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # If
+    /// `#if`, `#ifdef` and `#ifndef`. An invalid condition is
+    /// reported and considered false.
+    pub(super) fn if_directive(&mut self, directive: &str) -> Status {
+        let location = self.location;
+        let result = match directive {
+            "if" => self.condition(),
+            _ => self.macro_name().map(|name| self.is_defined(name) == (directive == "ifdef"))
+        };
+        let (taken, status) = match result {
+            Ok(taken) => (taken, ok!()),
+            Err(err) => (false, Some(err))
+        };
+        self.conds.push(Conditional {
+            state: if taken { CondState::Taking } else { CondState::Skipping },
+            location,
+            seen_else: false,
+            depth: self.includes.len(),
+        });
+        if !taken {
+            self.skip_group();
+        }
+        return status;
+    }
+
+    /// # Elif
+    /// `#elif`, `#elifdef` and `#elifndef` (the last two are C23).
+    /// The condition is only evaluated if no group has been
+    /// included yet.
+    pub(super) fn elif_directive(&mut self, directive: &str) -> Status {
+        let Some(cond) = self.current_conditional() else {
+            return error!(CompilerError::UNMATCHED_CONDITIONAL);
+        };
+        if cond.seen_else {
+            self.skip_group();
+            return error!(CompilerError::ELSE_AFTER_ELSE);
+        }
+        if cond.state != CondState::Skipping {
+            cond.state = CondState::Done;
+            self.skip_group();
+            return ok!();
+        }
+
+        let result = match directive {
+            "elif" => self.condition(),
+            _ => self.macro_name().map(|name| self.is_defined(name) == (directive == "elifdef"))
+        };
+        let (taken, status) = match result {
+            Ok(taken) => (taken, ok!()),
+            Err(err) => (false, Some(err))
+        };
+        if taken {
+            self.conds.last_mut().unwrap().state = CondState::Taking;
+        } else {
+            self.skip_group();
+        }
+        return status;
+    }
+
+    pub(super) fn else_directive(&mut self) -> Status {
+        let Some(cond) = self.current_conditional() else {
+            return error!(CompilerError::UNMATCHED_CONDITIONAL);
+        };
+        let seen_else = cond.seen_else;
+        cond.seen_else = true;
+        match cond.state {
+            CondState::Skipping if !seen_else => cond.state = CondState::Taking,
+            _ => {
+                cond.state = CondState::Done;
+                self.skip_group();
+            }
+        }
+        if seen_else {
+            return error!(CompilerError::ELSE_AFTER_ELSE);
+        }
+        return ok!();
+    }
+
+    pub(super) fn endif_directive(&mut self) -> Status {
+        if self.current_conditional().is_none() {
+            return error!(CompilerError::UNMATCHED_CONDITIONAL);
+        }
+        self.conds.pop();
+        return ok!();
+    }
+
+    /// The innermost conditional, if it was opened in the current file.
+    fn current_conditional(&mut self) -> Option<&mut Conditional> {
+        let depth = self.includes.len();
+        return self.conds.last_mut().filter(|cond| cond.depth == depth);
+    }
+
+    /// # Unterminated Conditionals
+    /// Reports and discards the conditionals left open at the
+    /// end of the current file.
+    pub(super) fn unterminated_conditionals(&mut self) {
+        while let Some(cond) = self.current_conditional() {
+            let location = cond.location;
+            self.conds.pop();
+            self.report(CompilerDiagnostic::Error(CompilerError::UNTERMINATED_CONDITIONAL), location);
+        }
+    }
+
+    /// # Skip Group
+    /// Throws away the rest of the directive line and every token
+    /// up to the `#elif`, `#else` or `#endif` closing the current
+    /// group, which is left on the input. Nested conditionals are
+    /// skipped entirely.
+    fn skip_group(&mut self) {
+        self.skip_line();
+        let mut depth = 0;
+        while let Some(tok) = self.input.pop() {
+            if tok.is(TokenType::EOF) {
+                self.input.push(tok);
+                return;
+            }
+            if !(tok.is(TokenType::HASH) && tok.bol) {
+                continue;
+            }
+            let name = match self.input.last() {
+                Some(name) if !name.bol => name.spelling,
+                _ => continue
+            };
+            match &*name {
+                "if" | "ifdef" | "ifndef" => depth += 1,
+                "endif" if depth > 0 => depth -= 1,
+                "elif" | "elifdef" | "elifndef" | "else" | "endif" if depth == 0 => {
+                    self.input.push(tok);
+                    return;
+                },
+                _ => ()
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! # Dependencies
+//! Every file opened by the preprocessor (the main source file,
+//! the `#include`d headers and the `#embed`ded resources) is
+//! recorded, so that builds know what to rebuild when something
+//! changes. `depfile()` writes them as a Make rule, like GCC's
+//! `-M` family of flags:
+//!
+//! ```make
+//! main.o: main.c config.h \
+//!  font.bin
+//! ```
+
+#![allow(clippy::needless_return)]
+
+use std::path::{Path, PathBuf};
+
+use super::Preprocessor;
+
+/// GCC's limit for the lines of a depfile, past which they are
+/// continued with a backslash.
+const MAX_LINE_LENGTH: usize = 75;
+
+#[derive(Debug, Clone)]
+/// # Dependency
+/// A file the output of the preprocessor depends on.
+pub struct Dependency {
+    pub path: PathBuf,
+    /// ## System
+    /// Whether the file was found in a system directory
+    /// (`-isystem` and the standard ones). `-MM` leaves
+    /// them out.
+    pub system: bool,
+}
+
+/// # Escape
+/// Escapes the characters Make treats specially in a file name.
+fn escape(path: &Path) -> String {
+    let mut escaped = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '\t' | '#' => escaped.push('\\'),
+            '$' => escaped.push('$'),
+            _ => ()
+        }
+        escaped.push(c);
+    }
+    return escaped;
+}
+
+/// # Rule
+/// Writes `targets: prerequisites`, continuing lines that
+/// would be too long with a backslash.
+fn rule(text: &mut String, targets: &[String], prerequisites: &[String]) {
+    let mut column = 0;
+    for (i, target) in targets.iter().enumerate() {
+        if i > 0 {
+            text.push(' ');
+            column += 1;
+        }
+        text.push_str(target);
+        column += target.len();
+    }
+    text.push(':');
+    column += 1;
+    for prerequisite in prerequisites {
+        if column + prerequisite.len() + 1 > MAX_LINE_LENGTH && column > 1 {
+            text.push_str(" \\\n");
+            column = 0;
+        }
+        text.push(' ');
+        text.push_str(prerequisite);
+        column += prerequisite.len() + 1;
+    }
+    text.push('\n');
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Get Dependencies
+    /// Returns every file opened so far, in the order they were
+    /// first opened. The main source file comes first.
+    pub fn get_dependencies(&self) -> &[Dependency] {
+        return &self.dependencies;
+    }
+
+    /// # Depfile
+    /// Returns a Make rule making `targets` depend on the files
+    /// opened by the preprocessor, leaving out the system ones
+    /// unless `system` is set (`-M` vs `-MM`). With `phony` (`-MP`)
+    /// every dependency but the main file also gets an empty rule
+    /// of its own, so that Make does not fail when it is deleted.
+    pub fn depfile(&self, targets: &[String], system: bool, phony: bool) -> String {
+        let dependencies: Vec<String> = self.dependencies.iter()
+            .filter(|dep| system || !dep.system)
+            .map(|dep| escape(&dep.path))
+            .collect();
+        let mut text = String::new();
+        rule(&mut text, targets, &dependencies);
+        if phony {
+            for dependency in dependencies.iter().skip(1) {
+                text.push('\n');
+                rule(&mut text, std::slice::from_ref(dependency), &[]);
+            }
+        }
+        return text;
+    }
+
+    /// # Add Dependency
+    /// Records that the file at `path` has been opened.
+    pub(super) fn add_dependency(&mut self, path: &Path) {
+        if self.dependencies.iter().any(|dep| dep.path == path) {
+            return;
+        }
+        let system = self.paths.is_system(path);
+        self.dependencies.push(Dependency { path: path.to_path_buf(), system });
+    }
+}
@@ -0,0 +1,219 @@
+//! # Embed
+//! C23's `#embed`, which includes the bytes of a file as a
+//! comma separated list of integer constants:
+//!
+//! ```c
+//! const unsigned char font[] = {
+//! #embed "font.bin" limit(4096) suffix(, 0)
+//! };
+//! ```
+//!
+//! The file is searched like an `#include`d one. The standard
+//! parameters are supported, also spelled `__name__`:
+//!
+//! - `limit(n)`: embeds at most `n` bytes
+//! - `prefix(tokens)` and `suffix(tokens)`: come before and
+//!   after the list, unless the file is empty
+//! - `if_empty(tokens)`: replaces the list if the file is empty
+//!
+//! Files can be large, so the bytes are kept together in a single
+//! `EMBED` token instead of becoming one token per byte.
+//!
+//! `#if` can check for a resource with `__has_embed`, which takes
+//! the same operand as `#embed` and is `__STDC_EMBED_NOT_FOUND__`
+//! (0) if the resource can't be found or one of the parameters is
+//! unknown, `__STDC_EMBED_EMPTY__` (2) if it is empty, limit
+//! included, and `__STDC_EMBED_FOUND__` (1) otherwise.
+
+#![allow(clippy::needless_return)]
+
+use std::fs::File;
+use std::io::Read;
+
+use crate::front::lex::TokenType;
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+
+use super::expr::{attribute_name, Value};
+use super::include::header_name;
+use super::token::PPToken;
+use super::Preprocessor;
+
+#[derive(Default)]
+/// # Parameters
+/// The parameters of an `#embed` directive, macro expanded.
+struct Parameters {
+    limit: Option<Vec<PPToken>>,
+    prefix: Option<Vec<PPToken>>,
+    suffix: Option<Vec<PPToken>>,
+    if_empty: Option<Vec<PPToken>>,
+}
+
+/// # Parse Parameters
+/// Reads the parameters following the resource name. Each of
+/// them is a name followed by a parenthesized, balanced list
+/// of tokens, and can only be given once.
+fn parse_parameters(mut line: &[PPToken]) -> Result<Parameters, CompilerDiagnostic> {
+    let malformed = || CompilerDiagnostic::Error(CompilerError::MALFORMED_EMBED);
+    let mut params = Parameters::default();
+    while !line.is_empty() {
+        // The name is either `name` or `vendor::name`
+        let len = match line {
+            [_, scope, _, ..] if scope.is(TokenType::DOUBLECOLON) => 3,
+            [_, colon, colon2, _, ..] if colon.is(TokenType::COLON) && colon2.is(TokenType::COLON) && !colon2.space => 4,
+            _ => 1
+        };
+        let name = attribute_name(&line[..len]).ok_or_else(malformed)?;
+        line = &line[len..];
+
+        match line.first() {
+            Some(lparen) if lparen.is(TokenType::LEFT_PAREN) => (),
+            _ => return Err(malformed())
+        }
+        let mut depth = 0;
+        let end = line.iter().position(|tok| {
+            match tok.tokentype {
+                TokenType::LEFT_PAREN => depth += 1,
+                TokenType::RIGHT_PAREN => depth -= 1,
+                _ => ()
+            }
+            return depth == 0;
+        }).ok_or_else(malformed)?;
+        let mut arg = line[1..end].to_vec();
+        line = &line[end + 1..];
+        if let Some(first) = arg.first_mut() {
+            first.space = false;
+        }
+
+        let param = match name.as_str() {
+            "limit" => &mut params.limit,
+            "prefix" => &mut params.prefix,
+            "suffix" => &mut params.suffix,
+            "if_empty" => &mut params.if_empty,
+            _ => return Err(CompilerDiagnostic::Error(CompilerError::UNKNOWN_EMBED_PARAMETER(name)))
+        };
+        if param.replace(arg).is_some() {
+            return Err(malformed());
+        }
+    }
+    return Ok(params);
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    pub(super) fn embed(&mut self) -> Status {
+        let location = self.location;
+        let mut line = vec![];
+        while let Some(tok) = self.next_in_line() {
+            line.push(tok);
+        }
+        let Some((name, angled, params)) = self.resource(line) else {
+            return error!(CompilerError::MALFORMED_EMBED);
+        };
+        let mut params = match parse_parameters(&params) {
+            Ok(params) => params,
+            Err(err) => return Some(err)
+        };
+        let limit = match self.limit(params.limit.take()) {
+            Ok(limit) => limit,
+            Err(err) => return Some(err)
+        };
+
+        let current = &self.includes.last().unwrap().path;
+        let Some(path) = self.paths.resolve(&name, angled, current) else {
+            return error!(CompilerError::INCLUDE_NOT_FOUND(name));
+        };
+        let mut bytes = vec![];
+        let read = File::open(&path).and_then(|file| file.take(limit).read_to_end(&mut bytes));
+        if read.is_err() {
+            return error!(CompilerError::INCLUDE_NOT_FOUND(name));
+        }
+        self.add_dependency(&path);
+
+        let mut tokens = match bytes.is_empty() {
+            true => params.if_empty.unwrap_or_default(),
+            false => {
+                let mut tokens = params.prefix.unwrap_or_default();
+                tokens.push(PPToken::new(TokenType::EMBED(bytes.into()), "".into(), location));
+                tokens.extend(params.suffix.unwrap_or_default());
+                tokens
+            }
+        };
+        // The result goes on a line of its own, but must not be
+        // mistaken for a directive
+        if let Some(first) = tokens.first_mut() {
+            first.bol = !first.is(TokenType::HASH);
+        }
+        self.input.extend(tokens.into_iter().rev());
+        return ok!();
+    }
+    /// # Has Embed
+    /// Returns the value of `__has_embed(operand)`, or `None`
+    /// if the operand is malformed.
+    pub(super) fn has_embed(&mut self, operand: Vec<PPToken>) -> Option<i64> {
+        const NOT_FOUND: i64 = 0;
+        const FOUND: i64 = 1;
+        const EMPTY: i64 = 2;
+
+        let (name, angled, params) = self.resource(operand)?;
+        let mut params = match parse_parameters(&params) {
+            Ok(params) => params,
+            Err(CompilerDiagnostic::Error(CompilerError::UNKNOWN_EMBED_PARAMETER(_))) => return Some(NOT_FOUND),
+            Err(_) => return None
+        };
+        let limit = self.limit(params.limit.take()).ok()?;
+        let current = &self.includes.last().unwrap().path;
+        let Some(path) = self.paths.resolve(&name, angled, current) else {
+            return Some(NOT_FOUND);
+        };
+        let mut byte = vec![];
+        match File::open(path).and_then(|file| file.take(limit.min(1)).read_to_end(&mut byte)) {
+            Ok(0) => return Some(EMPTY),
+            Ok(_) => return Some(FOUND),
+            Err(_) => return Some(NOT_FOUND)
+        }
+    }
+
+    /// # Resource
+    /// Splits the operand of `#embed` or `__has_embed` into the
+    /// name of the resource, whether it is `<angled>`, and the
+    /// macro expanded parameters. Either the operand starts with
+    /// a header name, like `"file" params`, or it is expanded as
+    /// a whole, like `MACRO...`.
+    fn resource(&mut self, mut line: Vec<PPToken>) -> Option<(String, bool, Vec<PPToken>)> {
+        let first = line.first()?;
+        if let TokenType::HEADER_NAME(ref name) = first.tokentype {
+            let angled = name.starts_with('<');
+            let name = name[1..name.len() - 1].to_owned();
+            let params = self.expand_all(line.split_off(1));
+            return Some((name, angled, params));
+        }
+        // In `__has_embed`, header names are lexed as other tokens
+        let written = first.is(TokenType::LESS) || first.spelling.starts_with('"');
+        if !written {
+            line = self.expand_all(line);
+        }
+        let (name, angled) = header_name(&line)?;
+        let len = match angled {
+            true => line.iter().position(|tok| tok.is(TokenType::GREATER)).unwrap() + 1,
+            false => 1
+        };
+        let params = line.split_off(len);
+        match written {
+            true => return Some((name, angled, self.expand_all(params))),
+            false => return Some((name, angled, params))
+        }
+    }
+
+    /// # Limit
+    /// Evaluates the operand of the `limit` parameter, if any.
+    fn limit(&mut self, limit: Option<Vec<PPToken>>) -> Result<u64, CompilerDiagnostic> {
+        let Some(limit) = limit else {
+            return Ok(u64::MAX);
+        };
+        match self.evaluate(limit)? {
+            Value::Signed(limit) if limit < 0 => return Err(CompilerDiagnostic::Error(CompilerError::MALFORMED_EMBED)),
+            Value::Signed(limit) => return Ok(limit as u64),
+            Value::Unsigned(limit) => return Ok(limit)
+        }
+    }
+}
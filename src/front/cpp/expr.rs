@@ -0,0 +1,476 @@
+//! # Conditional Expressions
+//! The evaluator for the expressions of `#if` and `#elif`.
+//!
+//! Expressions are evaluated with the semantics of `intmax_t`
+//! and `uintmax_t` (C99 6.10.1p4), which are 64 bits wide on
+//! every target we care about. Operands are converted to
+//! unsigned as soon as one of them is unsigned, just like the
+//! usual arithmetic conversions do.
+//!
+//! `&&`, `||` and `?:` short-circuit: the operands that are not
+//! evaluated are still parsed, but cannot trigger errors like a
+//! division by zero.
+//!
+//! Besides `defined`, conditions can probe the compiler with
+//! `__has_include(<file>)`, `__has_attribute(name)`,
+//! `__has_c_attribute(name)`, `__has_builtin(name)`, and clang's
+//! `__has_feature(name)` and `__has_extension(name)`, and C23's
+//! `__has_embed("file" params)`. Apart from `__has_include` and
+//! `__has_embed`, the answers come from the tables in `util::lang`.
+
+#![allow(clippy::needless_return)]
+
+use std::mem;
+
+use crate::front::lex::{Atom, TokenType};
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::escape;
+use crate::util::intern::Symbol;
+use crate::util::lang;
+
+use super::include::header_name;
+use super::token::PPToken;
+use super::Preprocessor;
+
+/// The operators probing the compiler. They count as defined
+/// macros, so that `#ifdef __has_include` works as expected.
+const QUERIES: [&str; 7] = [
+    "__has_include", "__has_embed", "__has_attribute", "__has_c_attribute", "__has_builtin", "__has_feature",
+    "__has_extension"
+];
+
+/// # Attribute Name
+/// Reads the operand of the attribute and feature queries: a
+/// name, possibly scoped (`gnu::packed`). `__name__` is the same
+/// as `name`, like in attributes. Before C23 there is no `::`
+/// punctuator, so the scope is followed by two colons instead.
+pub(super) fn attribute_name(operand: &[PPToken]) -> Option<String> {
+    let normalize = |tok: &PPToken| -> Option<String> {
+        let name = tok.ident()?;
+        let name = name.strip_prefix("__").and_then(|name| name.strip_suffix("__")).unwrap_or(name);
+        return Some(name.to_owned());
+    };
+    match operand {
+        [name] => return normalize(name),
+        [scope, colons, name] if colons.is(TokenType::DOUBLECOLON) => {
+            return Some(format!("{}::{}", normalize(scope)?, normalize(name)?));
+        },
+        [scope, colon, colon2, name] if colon.is(TokenType::COLON) && colon2.is(TokenType::COLON) && !colon2.space => {
+            return Some(format!("{}::{}", normalize(scope)?, normalize(name)?));
+        },
+        _ => return None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Signed(i64),
+    Unsigned(u64),
+}
+
+impl Value {
+    fn truthy(self) -> bool {
+        return self != Value::Signed(0) && self != Value::Unsigned(0);
+    }
+
+    fn unsigned(self) -> u64 {
+        match self {
+            Value::Signed(value) => return value as u64,
+            Value::Unsigned(value) => return value,
+        }
+    }
+
+    fn bool(value: bool) -> Value {
+        return Value::Signed(value as i64);
+    }
+}
+
+type Eval = Result<Value, CompilerDiagnostic>;
+
+fn malformed<T>() -> Result<T, CompilerDiagnostic> {
+    return Err(CompilerDiagnostic::Error(CompilerError::MALFORMED_CONDITION));
+}
+
+/// # Number
+/// Parses an integer constant, suffixes and C23 digit
+/// separators included.
+fn number(spelling: &str) -> Eval {
+    let spelling = spelling.replace('\'', "");
+    let (digits, radix) = match spelling.as_bytes() {
+        [b'0', b'x' | b'X', ..] => (&spelling[2..], 16),
+        [b'0', b'b' | b'B', ..] => (&spelling[2..], 2),
+        [b'0', b'0' ..= b'9', ..] => (&spelling[1..], 8),
+        _ => (spelling.as_str(), 10)
+    };
+    let end = match radix {
+        16 => digits.find(|c: char| !c.is_ascii_hexdigit()),
+        _ => digits.find(|c: char| !c.is_ascii_digit())
+    };
+    let (digits, suffix) = digits.split_at(end.unwrap_or(digits.len()));
+    if !is_integer_suffix(suffix) {
+        return Err(CompilerDiagnostic::Error(CompilerError::INVALID_INTEGER_SUFFIX(suffix.to_owned())));
+    }
+    let Ok(value) = u64::from_str_radix(digits, radix) else {
+        return Err(CompilerDiagnostic::Error(CompilerError::MALFORMED_NUMBER));
+    };
+    // Constants that do not fit in intmax_t are unsigned
+    if suffix.contains(['u', 'U']) || value > i64::MAX as u64 {
+        return Ok(Value::Unsigned(value));
+    }
+    return Ok(Value::Signed(value as i64));
+}
+
+/// # Integer suffix
+/// Whether `suffix` is an optional `u` next to an optional
+/// `l`, `ll` or `wb`, in either order, like `ULL` or `lu`.
+fn is_integer_suffix(suffix: &str) -> bool {
+    let unsigned = |suffix: &str| suffix.strip_prefix(['u', 'U']).unwrap_or(suffix).is_empty();
+    for size in ["", "l", "L", "ll", "LL", "wb", "WB"] {
+        if suffix.strip_prefix(size).is_some_and(unsigned) {
+            return true;
+        }
+        if suffix.strip_suffix(size).is_some_and(unsigned) {
+            return true;
+        }
+    }
+    return false;
+}
+
+struct Evaluator<'eval> {
+    tokens: &'eval [PPToken],
+    pos: usize,
+}
+
+impl<'eval> Evaluator<'eval> {
+    fn peek(&self) -> Option<&TokenType> {
+        return self.tokens.get(self.pos).map(|tok| &tok.tokentype);
+    }
+
+    fn accept(&mut self, tokentype: TokenType) -> bool {
+        if self.peek() == Some(&tokentype) {
+            self.pos += 1;
+            return true;
+        }
+        return false;
+    }
+
+    // expression -> conditional ( "," conditional )*
+    fn expression(&mut self, eval: bool) -> Eval {
+        let mut value = self.conditional(eval)?;
+        while self.accept(TokenType::COMMA) {
+            value = self.conditional(eval)?;
+        }
+        return Ok(value);
+    }
+
+    // conditional -> logical_or ( "?" expression ":" conditional )?
+    fn conditional(&mut self, eval: bool) -> Eval {
+        let cond = self.logical_or(eval)?;
+        if !self.accept(TokenType::QUESTION) {
+            return Ok(cond);
+        }
+        let then = self.expression(eval && cond.truthy())?;
+        if !self.accept(TokenType::COLON) {
+            return malformed();
+        }
+        let otherwise = self.conditional(eval && !cond.truthy())?;
+        let value = if cond.truthy() { then } else { otherwise };
+        // Both branches take part in the type of the result
+        match (then, otherwise) {
+            (Value::Signed(_), Value::Signed(_)) => return Ok(value),
+            _ => return Ok(Value::Unsigned(value.unsigned()))
+        }
+    }
+
+    // logical_or -> logical_and ( "||" logical_and )*
+    fn logical_or(&mut self, eval: bool) -> Eval {
+        let mut lhs = self.logical_and(eval)?;
+        while self.accept(TokenType::DOUBLEBAR) {
+            let rhs = self.logical_and(eval && !lhs.truthy())?;
+            lhs = Value::bool(lhs.truthy() || rhs.truthy());
+        }
+        return Ok(lhs);
+    }
+
+    // logical_and -> binary ( "&&" binary )*
+    fn logical_and(&mut self, eval: bool) -> Eval {
+        let mut lhs = self.binary(0, eval)?;
+        while self.accept(TokenType::DOUBLEAMPERSAND) {
+            let rhs = self.binary(0, eval && lhs.truthy())?;
+            lhs = Value::bool(lhs.truthy() && rhs.truthy());
+        }
+        return Ok(lhs);
+    }
+
+    /// # Binary
+    /// Parses the binary operators from `|` down to `*`, using
+    /// precedence climbing. Higher levels bind tighter.
+    fn binary(&mut self, level: usize, eval: bool) -> Eval {
+        const LEVELS: [&[TokenType]; 8] = [
+            &[TokenType::BAR],
+            &[TokenType::CARET],
+            &[TokenType::AMPERSAND],
+            &[TokenType::DOUBLEEQ, TokenType::NEQ],
+            &[TokenType::LESS, TokenType::LESSEQ, TokenType::GREATER, TokenType::GREATEREQ],
+            &[TokenType::DOUBLELESS, TokenType::DOUBLEGREATER],
+            &[TokenType::PLUS, TokenType::MINUS],
+            &[TokenType::ASTERISK, TokenType::SLASH, TokenType::PERCENT],
+        ];
+        if level == LEVELS.len() {
+            return self.unary(eval);
+        }
+        let mut lhs = self.binary(level + 1, eval)?;
+        while let Some(op) = self.peek().filter(|op| LEVELS[level].contains(op)).cloned() {
+            self.pos += 1;
+            let rhs = self.binary(level + 1, eval)?;
+            lhs = apply(op, lhs, rhs, eval)?;
+        }
+        return Ok(lhs);
+    }
+
+    // unary -> ( "+" | "-" | "~" | "!" ) unary | primary
+    fn unary(&mut self, eval: bool) -> Eval {
+        let op = match self.peek() {
+            Some(op @ (TokenType::PLUS | TokenType::MINUS | TokenType::TILDE | TokenType::BANG)) => op.clone(),
+            _ => return self.primary(eval)
+        };
+        self.pos += 1;
+        let value = self.unary(eval)?;
+        match (op, value) {
+            (TokenType::PLUS, value) => return Ok(value),
+            (TokenType::MINUS, Value::Signed(value)) => return Ok(Value::Signed(value.wrapping_neg())),
+            (TokenType::MINUS, Value::Unsigned(value)) => return Ok(Value::Unsigned(value.wrapping_neg())),
+            (TokenType::TILDE, Value::Signed(value)) => return Ok(Value::Signed(!value)),
+            (TokenType::TILDE, Value::Unsigned(value)) => return Ok(Value::Unsigned(!value)),
+            (_, value) => return Ok(Value::bool(!value.truthy())),
+        }
+    }
+
+    // primary -> NUMBER | CHAR | IDENTIFIER | "(" expression ")"
+    fn primary(&mut self, eval: bool) -> Eval {
+        let Some(tok) = self.tokens.get(self.pos) else {
+            return malformed();
+        };
+        self.pos += 1;
+        match tok.tokentype {
+            TokenType::ATOM(Atom::NUM(ref num)) => return number(num),
+            TokenType::ATOM(Atom::CHAR(encoding, ref chr)) => {
+                let mut diag = vec![];
+                let units = escape::unescape(chr, encoding, &mut diag);
+                match diag.into_iter().find(|diag| matches!(diag, CompilerDiagnostic::Error(_))) {
+                    Some(error) => return Err(error),
+                    None => return Ok(Value::Signed(escape::char_value(&units, encoding)))
+                }
+            },
+            TokenType::LEFT_PAREN => {
+                let value = self.expression(eval)?;
+                if !self.accept(TokenType::RIGHT_PAREN) {
+                    return malformed();
+                }
+                return Ok(value);
+            },
+            // C23: `true` is 1, while `false` is 0 like any other identifier
+            TokenType::TRUE => return Ok(Value::Signed(1)),
+            // Identifiers left after macro expansion are replaced by 0
+            _ if tok.ident().is_some() => return Ok(Value::Signed(0)),
+            _ => return malformed()
+        }
+    }
+}
+
+fn apply(op: TokenType, lhs: Value, rhs: Value, eval: bool) -> Eval {
+    // Shifts have the type of their left operand
+    match op {
+        TokenType::DOUBLELESS | TokenType::DOUBLEGREATER => {
+            let amount = match rhs {
+                Value::Unsigned(amount) => amount.min(64) as i64,
+                Value::Signed(amount) => amount.clamp(-64, 64),
+            };
+            // A negative shift goes the other way, like in GCC
+            let left = (op == TokenType::DOUBLELESS) == (amount >= 0);
+            let amount = amount.unsigned_abs() as u32;
+            match (lhs, left) {
+                (Value::Signed(value), true) => return Ok(Value::Signed(value.checked_shl(amount).unwrap_or(0))),
+                (Value::Signed(value), false) => return Ok(Value::Signed(value >> amount.min(63))),
+                (Value::Unsigned(value), true) => return Ok(Value::Unsigned(value.checked_shl(amount).unwrap_or(0))),
+                (Value::Unsigned(value), false) => return Ok(Value::Unsigned(value.checked_shr(amount).unwrap_or(0))),
+            }
+        },
+        TokenType::SLASH | TokenType::PERCENT if !rhs.truthy() => {
+            if eval {
+                return Err(CompilerDiagnostic::Error(CompilerError::DIVISION_BY_ZERO));
+            }
+            return Ok(Value::Signed(0));
+        },
+        _ => ()
+    }
+
+    if let (Value::Signed(lhs), Value::Signed(rhs)) = (lhs, rhs) {
+        let value = match op {
+            TokenType::ASTERISK => lhs.wrapping_mul(rhs),
+            TokenType::SLASH => lhs.wrapping_div(rhs),
+            TokenType::PERCENT => lhs.wrapping_rem(rhs),
+            TokenType::PLUS => lhs.wrapping_add(rhs),
+            TokenType::MINUS => lhs.wrapping_sub(rhs),
+            TokenType::AMPERSAND => lhs & rhs,
+            TokenType::CARET => lhs ^ rhs,
+            TokenType::BAR => lhs | rhs,
+            TokenType::LESS => (lhs < rhs) as i64,
+            TokenType::LESSEQ => (lhs <= rhs) as i64,
+            TokenType::GREATER => (lhs > rhs) as i64,
+            TokenType::GREATEREQ => (lhs >= rhs) as i64,
+            TokenType::DOUBLEEQ => (lhs == rhs) as i64,
+            TokenType::NEQ => (lhs != rhs) as i64,
+            _ => unreachable!()
+        };
+        return Ok(Value::Signed(value));
+    }
+
+    let (lhs, rhs) = (lhs.unsigned(), rhs.unsigned());
+    let value = match op {
+        TokenType::ASTERISK => lhs.wrapping_mul(rhs),
+        TokenType::SLASH => lhs / rhs,
+        TokenType::PERCENT => lhs % rhs,
+        TokenType::PLUS => lhs.wrapping_add(rhs),
+        TokenType::MINUS => lhs.wrapping_sub(rhs),
+        TokenType::AMPERSAND => lhs & rhs,
+        TokenType::CARET => lhs ^ rhs,
+        TokenType::BAR => lhs | rhs,
+        // Comparisons are always signed ints
+        TokenType::LESS => return Ok(Value::bool(lhs < rhs)),
+        TokenType::LESSEQ => return Ok(Value::bool(lhs <= rhs)),
+        TokenType::GREATER => return Ok(Value::bool(lhs > rhs)),
+        TokenType::GREATEREQ => return Ok(Value::bool(lhs >= rhs)),
+        TokenType::DOUBLEEQ => return Ok(Value::bool(lhs == rhs)),
+        TokenType::NEQ => return Ok(Value::bool(lhs != rhs)),
+        _ => unreachable!()
+    };
+    return Ok(Value::Unsigned(value));
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Condition
+    /// Reads the rest of the line and evaluates it as the
+    /// expression of an `#if` or `#elif` directive.
+    pub(super) fn condition(&mut self) -> Result<bool, CompilerDiagnostic> {
+        let mut line = vec![];
+        while let Some(tok) = self.next_in_line() {
+            line.push(tok);
+        }
+        return self.evaluate(line).map(|value| value.truthy());
+    }
+
+    /// # Evaluate
+    /// Evaluates `tokens` as a preprocessor constant expression.
+    pub(super) fn evaluate(&mut self, tokens: Vec<PPToken>) -> Result<Value, CompilerDiagnostic> {
+        let tokens = self.expand_condition(tokens)?;
+        let mut evaluator = Evaluator { tokens: &tokens, pos: 0 };
+        let value = evaluator.expression(true)?;
+        if evaluator.pos != tokens.len() {
+            return malformed();
+        }
+        return Ok(value);
+    }
+
+    /// # Expand Condition
+    /// Macro-expands the tokens of a condition, replacing every
+    /// `defined X`, `defined(X)` and query like `__has_include(X)`
+    /// with its value.
+    fn expand_condition(&mut self, line: Vec<PPToken>) -> Result<Vec<PPToken>, CompilerDiagnostic> {
+        let saved = mem::replace(&mut self.input, line.into_iter().rev().collect());
+        let mut expanded = vec![];
+        let mut status = Ok(());
+        while let Some(tok) = self.input.pop() {
+            let value = match tok.ident() {
+                Some("defined") => self.defined().map(i64::from),
+                Some(query) if QUERIES.contains(&query) => self.query(query),
+                _ => {
+                    if !self.expand_macro(&tok) {
+                        expanded.push(tok);
+                    }
+                    continue;
+                }
+            };
+            let Some(value) = value else {
+                status = malformed();
+                break;
+            };
+            let value = value.to_string();
+            let num = TokenType::ATOM(Atom::NUM(value.clone()));
+            expanded.push(PPToken::new(num, value.into(), tok.location));
+        }
+        self.input = saved;
+        return status.map(|_| expanded);
+    }
+
+    /// # Query
+    /// Reads the parenthesized operand of a query, and returns
+    /// its value: 0 for no, and usually 1 for yes.
+    fn query(&mut self, query: &str) -> Option<i64> {
+        if !self.input.pop()?.is(TokenType::LEFT_PAREN) {
+            return None;
+        }
+        let mut operand = vec![];
+        let mut depth = 0;
+        loop {
+            let tok = self.input.pop()?;
+            match tok.tokentype {
+                TokenType::LEFT_PAREN => depth += 1,
+                TokenType::RIGHT_PAREN if depth == 0 => break,
+                TokenType::RIGHT_PAREN => depth -= 1,
+                _ => ()
+            }
+            operand.push(tok);
+        }
+
+        if query == "__has_include" {
+            // Like in #include, the operand is macro expanded only
+            // if it is not already a header name.
+            let header = match operand.first() {
+                Some(tok) if tok.is(TokenType::LESS) || tok.spelling.starts_with('"') => header_name(&operand),
+                _ => header_name(&self.expand_all(operand))
+            };
+            let (name, angled) = header?;
+            let current = &self.includes.last().unwrap().path;
+            return Some(i64::from(self.paths.resolve(&name, angled, current).is_some()));
+        }
+        if query == "__has_embed" {
+            return self.has_embed(operand);
+        }
+        let name = attribute_name(&operand)?;
+        let found = |table: &[&str]| i64::from(table.contains(&name.as_str()));
+        match query {
+            "__has_attribute" => return Some(found(lang::ATTRIBUTES)),
+            "__has_builtin" => return Some(found(lang::BUILTINS)),
+            "__has_c_attribute" => {
+                let value = lang::C_ATTRIBUTES.iter().find(|(attribute, _)| *attribute == name);
+                return Some(value.map_or(0, |(_, value)| *value));
+            },
+            _ => return Some(found(lang::FEATURES))
+        }
+    }
+
+    /// # Defined
+    /// Reads the operand of `defined`, which must not be
+    /// expanded, and tells whether it is a macro.
+    fn defined(&mut self) -> Option<bool> {
+        let mut tok = self.input.pop()?;
+        let paren = tok.is(TokenType::LEFT_PAREN);
+        if paren {
+            tok = self.input.pop()?;
+        }
+        let defined = self.is_defined(tok.name()?);
+        if paren && !self.input.pop()?.is(TokenType::RIGHT_PAREN) {
+            return None;
+        }
+        return Some(defined);
+    }
+
+    /// # Is Defined
+    /// Whether `name` is a macro, as far as `defined`,
+    /// `#ifdef` and `#ifndef` are concerned.
+    pub(super) fn is_defined(&self, name: Symbol) -> bool {
+        return self.macros.contains_key(&name) || QUERIES.contains(&name.as_str());
+    }
+}
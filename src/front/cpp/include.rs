@@ -0,0 +1,328 @@
+//! # Include
+//! `#include` resolution and the include stack.
+//!
+//! The search order is the same as GCC's:
+//!
+//! - `#include "file"`: the directory of the current file,
+//!   then the `quote` (`-iquote`) directories and then the
+//!   same directories searched by `#include <file>`.
+//! - `#include <file>`: the `angled` (`-I`) directories and
+//!   then the `system` (`-isystem`) ones.
+//!
+//! Every file is lexed only once: its tokens are cached and
+//! reused by the following inclusions. Files containing
+//! `#pragma once` are never included again, and neither are
+//! the ones wrapped in a classic include guard whose macro is
+//! defined:
+//!
+//! ```c
+//! #ifndef HEADER_H // or #if !defined(HEADER_H)
+//! #define HEADER_H
+//! ...
+//! #endif
+//! ```
+
+#![allow(clippy::needless_return)]
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::front::lex::{Lexer, TokenType};
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::intern::Symbol;
+use crate::util::scan::location::Location;
+
+use super::print::Linemarker;
+use super::token::{self, PPToken};
+use super::Preprocessor;
+
+/// GCC's limit, deep enough for any sane project.
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+/// # Multiarch
+/// The Debian multiarch name of the target, whose system
+/// headers are in `/usr/include/<multiarch>`.
+fn multiarch() -> Option<&'static str> {
+    if !cfg!(all(target_os = "linux", target_env = "gnu")) {
+        return None;
+    }
+    if cfg!(target_arch = "x86_64") {
+        return Some("x86_64-linux-gnu");
+    } else if cfg!(target_arch = "x86") {
+        return Some("i386-linux-gnu");
+    } else if cfg!(target_arch = "aarch64") {
+        return Some("aarch64-linux-gnu");
+    } else if cfg!(target_arch = "riscv64") {
+        return Some("riscv64-linux-gnu");
+    }
+    return None;
+}
+
+#[derive(Debug, Default, Clone)]
+/// # SearchPaths
+/// The directories searched by `#include`, in order.
+pub struct SearchPaths {
+    /// ## Quote
+    /// Searched only by `#include "file"` (`-iquote`).
+    pub quote: Vec<PathBuf>,
+    /// ## Angled
+    /// User include directories (`-I`).
+    pub angled: Vec<PathBuf>,
+    /// ## System
+    /// System include directories (`-isystem`), searched last.
+    pub system: Vec<PathBuf>,
+}
+
+impl SearchPaths {
+    /// # Add Standard
+    /// Adds the system directories searched unless `-nostdinc`
+    /// is given, in GCC's order: the compiler's own headers, in
+    /// `lib/tornado-cc/include` next to its `bin` directory, then
+    /// `/usr/local/include`, the multiarch directory of the target
+    /// and `/usr/include`.
+    ///
+    /// The freestanding headers, like `<stddef.h>` and `<stdarg.h>`,
+    /// belong to the compiler and are not shipped yet, so they have
+    /// to be provided with `-isystem`.
+    pub fn add_standard(&mut self) {
+        let exe = std::env::current_exe().ok();
+        let prefix = exe.as_deref().and_then(Path::parent).and_then(Path::parent);
+        if let Some(dir) = prefix.map(|prefix| prefix.join("lib/tornado-cc/include")).filter(|dir| dir.is_dir()) {
+            self.system.push(dir);
+        }
+        self.system.push("/usr/local/include".into());
+        if let Some(multiarch) = multiarch() {
+            self.system.push(Path::new("/usr/include").join(multiarch));
+        }
+        self.system.push("/usr/include".into());
+    }
+
+    /// # Resolve
+    /// Finds the file named `name`. `current` is the path of the
+    /// file containing the `#include` directive.
+    pub fn resolve(&self, name: &str, angled: bool, current: &Path) -> Option<PathBuf> {
+        let name = Path::new(name);
+        if name.is_absolute() {
+            return name.is_file().then(|| name.to_path_buf());
+        }
+        let mut dirs = vec![];
+        if !angled {
+            dirs.push(current.parent().unwrap_or(Path::new("")));
+            dirs.extend(self.quote.iter().map(PathBuf::as_path));
+        }
+        dirs.extend(self.angled.iter().map(PathBuf::as_path));
+        dirs.extend(self.system.iter().map(PathBuf::as_path));
+        return dirs.into_iter().map(|dir| dir.join(name)).find(|path| path.is_file());
+    }
+
+    /// # Is System
+    /// Whether `path` was found in a system directory.
+    pub fn is_system(&self, path: &Path) -> bool {
+        return self.system.iter().any(|dir| path.starts_with(dir));
+    }
+}
+
+/// # Include
+/// An entry of the include stack.
+pub(super) struct Include {
+    /// ## Path
+    /// The path the file was actually read from.
+    pub path: PathBuf,
+    /// ## Origin
+    /// The file as reported by diagnostics and `__FILE__`,
+    /// which `#line` can change.
+    pub origin: Rc<Origin>,
+    /// ## Location
+    /// Where the file was included from, in the includer.
+    pub location: Location,
+}
+
+/// # Header
+/// A file that has already been lexed.
+pub(super) struct Header {
+    tokens: Rc<[PPToken]>,
+    /// ## Guard
+    /// The macro of the include guard wrapping the whole
+    /// file, if any.
+    guard: Option<Symbol>,
+}
+
+/// # Headers
+/// The files included so far, by canonical path.
+#[derive(Default)]
+pub(super) struct Headers {
+    cache: HashMap<PathBuf, Header>,
+    /// ## Once
+    /// The files containing `#pragma once`.
+    once: HashSet<PathBuf>,
+}
+
+/// # Canonical
+/// The same file can be reached through different paths
+/// (`a.h`, `./a.h`, symlinks...), so files are identified
+/// by their canonical path.
+fn canonical(path: &Path) -> PathBuf {
+    return fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+}
+
+/// # Include Guard
+/// Returns the macro of the include guard wrapping all of
+/// `tokens`. The guard must start at the first token and
+/// its `#endif` must be the last directive of the file,
+/// with no `#else` or `#elif` in between.
+fn include_guard(tokens: &[PPToken]) -> Option<Symbol> {
+    if !tokens.first()?.is(TokenType::HASH) {
+        return None;
+    }
+    let line: Vec<&str> = tokens[1..].iter()
+        .take_while(|tok| !tok.bol && !tok.is(TokenType::EOF))
+        .map(|tok| &*tok.spelling)
+        .collect();
+    let guard = match line.as_slice() {
+        ["ifndef", guard] | ["if", "!", "defined", guard] | ["if", "!", "defined", "(", guard, ")"] => *guard,
+        _ => return None
+    };
+
+    let mut depth = 0;
+    let directives = tokens.iter().enumerate()
+        .filter(|(i, tok)| tok.is(TokenType::HASH) && tok.bol && *i + 1 < tokens.len());
+    for (i, _) in directives {
+        match tokens[i + 1].ident() {
+            Some("if" | "ifdef" | "ifndef") => depth += 1,
+            Some("elif" | "elifdef" | "elifndef" | "else") if depth == 1 => return None,
+            Some("endif") => {
+                depth -= 1;
+                if depth == 0 {
+                    // Only the rest of the #endif line may follow
+                    let rest = tokens[i + 1..].iter().find(|tok| tok.bol || tok.is(TokenType::EOF))?;
+                    return rest.is(TokenType::EOF).then(|| guard.into());
+                }
+            },
+            _ => ()
+        }
+    }
+    return None;
+}
+
+/// # Header Name
+/// Builds a header name out of the tokens of a macro expanded
+/// `#include` line. Returns the name and whether it is a
+/// `<file>` (angled) one.
+pub(super) fn header_name(line: &[PPToken]) -> Option<(String, bool)> {
+    let first = line.first()?;
+    if first.is(TokenType::LESS) {
+        let mut name = String::new();
+        for tok in line[1..].iter() {
+            if tok.is(TokenType::GREATER) {
+                return Some((name, true));
+            }
+            if tok.space && !name.is_empty() {
+                name.push(' ');
+            }
+            name.push_str(&tok.spelling);
+        }
+        return None;
+    }
+    let spelling = first.spelling.strip_prefix('"')?.strip_suffix('"')?;
+    return Some((spelling.to_owned(), false));
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    pub(super) fn include(&mut self) -> Status {
+        let location = self.location;
+        let Some(tok) = self.next_in_line() else {
+            return error!(CompilerError::MALFORMED_INCLUDE);
+        };
+
+        let header = match tok.tokentype {
+            TokenType::HEADER_NAME(ref name) => {
+                let angled = name.starts_with('<');
+                Some((name[1..name.len() - 1].to_owned(), angled))
+            },
+            // #include MACRO
+            _ => {
+                let mut line = vec![tok];
+                while let Some(tok) = self.next_in_line() {
+                    line.push(tok);
+                }
+                header_name(&self.expand_all(line))
+            }
+        };
+        let Some((name, angled)) = header else {
+            return error!(CompilerError::MALFORMED_INCLUDE);
+        };
+
+        if self.includes.len() >= MAX_INCLUDE_DEPTH {
+            return error!(CompilerError::INCLUDE_TOO_DEEP);
+        }
+        let current = &self.includes.last().unwrap().path;
+        let Some(path) = self.paths.resolve(&name, angled, current) else {
+            return error!(CompilerError::INCLUDE_NOT_FOUND(name));
+        };
+
+        let key = canonical(&path);
+        if self.headers.once.contains(&key) {
+            return ok!();
+        }
+        if let Some(Header { guard: Some(guard), .. }) = self.headers.cache.get(&key) {
+            if self.macros.contains_key(guard) {
+                return ok!();
+            }
+        }
+        return self.push_file(path, location);
+    }
+
+    /// # Push File
+    /// Pushes the tokens of the file at `path` onto the input,
+    /// together with a new entry of the include stack. The entry
+    /// is popped once the EOF of the file is reached. The file is
+    /// lexed only the first time it is pushed.
+    pub(super) fn push_file(&mut self, path: PathBuf, location: Location) -> Status {
+        let included_from = self.includes.last().map(|parent| (parent.origin.clone(), location));
+        let origin = Rc::new(Origin { file: path.clone(), included_from, delta: 0 });
+
+        let key = canonical(&path);
+        let tokens = match self.headers.cache.get(&key) {
+            Some(header) => header.tokens.clone(),
+            None => {
+                let Ok(file) = self.sources.load(&path) else {
+                    return error!(CompilerError::INCLUDE_NOT_FOUND(path.display().to_string()));
+                };
+                self.add_dependency(&path);
+                let first_diag = self.diag.len();
+                let source = self.sources.source(file);
+                let mut lexer = Lexer::new(source, file, self.diag);
+                lexer.set_trigraphs(self.trigraphs);
+                lexer.set_standard(self.standard);
+                lexer.set_extensions(self.extensions);
+                let tokens: Rc<[PPToken]> = token::from_tokens(source, lexer, self.trigraphs).into();
+                // The lexer knows nothing about the include stack
+                for diag in self.diag[first_diag..].iter_mut() {
+                    diag.origin = Some(origin.clone());
+                }
+                let warnings = self.diag.extract_if(first_diag.., |diag| matches!(diag.diagnostic, CompilerDiagnostic::Warning(_)));
+                self.lexed.extend(warnings);
+                let guard = include_guard(&tokens);
+                self.headers.cache.insert(key, Header { tokens: tokens.clone(), guard });
+                tokens
+            }
+        };
+
+        self.linemarkers.push(Linemarker {
+            index: self.output.len(), origin: origin.clone(), line: 0, physical: 0, flag: Some(1)
+        });
+        self.includes.push(Include { path, origin, location });
+        self.input.extend(tokens.iter().rev().cloned());
+        return ok!();
+    }
+
+    /// # Pragma Once
+    /// Marks the current file as never to be included again.
+    pub(super) fn pragma_once(&mut self) {
+        let key = canonical(&self.includes.last().unwrap().path);
+        self.headers.once.insert(key);
+    }
+}
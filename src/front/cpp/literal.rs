@@ -0,0 +1,99 @@
+//! # Literals
+//! String literals and character constants leave the
+//! preprocessor the way they were written. On the way out
+//! their escape sequences are checked (translation phase 5)
+//! and adjacent string literals are concatenated (phase 6):
+//! `"Hello, " "world"` is the same as `"Hello, world"`.
+//!
+//! When concatenating, a literal with an encoding prefix makes
+//! the whole result use it: `"a" L"b"` is `L"ab"`. Different
+//! prefixes can't be mixed.
+
+#![allow(clippy::needless_return)]
+
+use crate::front::lex::{Atom, TokenType};
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::diag::warn::CompilerWarning;
+use crate::util::escape::{self, Encoding};
+
+use super::token::PPToken;
+use super::Preprocessor;
+
+/// # Encoding Of
+/// The encoding of the concatenation of two string literals,
+/// if they can be concatenated.
+fn encoding_of(a: Encoding, b: Encoding) -> Option<Encoding> {
+    match (a, b) {
+        (Encoding::Char, other) | (other, Encoding::Char) => return Some(other),
+        _ if a == b => return Some(a),
+        _ => return None
+    }
+}
+
+/// # Concatenate
+/// Returns the encoding and the contents of the string literal
+/// made of `strings`. Escape sequences are replaced before
+/// concatenating, so that `"\x1" "2"` stays two characters
+/// instead of becoming `"\x12"`.
+pub(super) fn concatenate(strings: &[PPToken]) -> (Encoding, String) {
+    let strings: Vec<_> = strings.iter().filter_map(|tok| match tok.tokentype {
+        TokenType::ATOM(Atom::STRING(encoding, ref raw)) => Some((encoding, raw)),
+        _ => None
+    }).collect();
+    // Mismatched prefixes have been reported by check_literal()
+    let encoding = strings.iter().fold(Encoding::Char, |acc, (encoding, _)| {
+        encoding_of(acc, *encoding).unwrap_or(acc)
+    });
+    let mut units = vec![];
+    for (_, raw) in strings {
+        // And so have malformed escape sequences
+        units.extend(escape::unescape(raw, encoding, &mut vec![]));
+    }
+    return (encoding, escape::escape(&units));
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Check Literal
+    /// Reports the malformed escape sequences of `tok`, if it is a
+    /// string literal or a character constant, and the character
+    /// constants that are empty or made of multiple characters.
+    pub(super) fn check_literal(&mut self, tok: &PPToken) {
+        let (encoding, raw, char) = match tok.tokentype {
+            TokenType::ATOM(Atom::STRING(encoding, raw)) => (encoding, raw.as_str(), false),
+            TokenType::ATOM(Atom::CHAR(encoding, ref raw)) => (encoding, raw.as_str(), true),
+            _ => return
+        };
+        let mut diag = vec![];
+        let units = escape::unescape(raw, encoding, &mut diag);
+        match units.len() {
+            0 if char => diag.push(CompilerDiagnostic::Error(CompilerError::EMPTY_CHAR_CONSTANT)),
+            1 => (),
+            _ if char => diag.push(CompilerDiagnostic::Warning(CompilerWarning::MULTICHAR_CONSTANT)),
+            _ => ()
+        }
+        for diagnostic in diag {
+            self.report_at(diagnostic, tok);
+        }
+    }
+
+    /// # Check Concatenation
+    /// Records the error of `tok` if it is a string literal that
+    /// can't be concatenated to the ones before it, to be reported
+    /// by `finish()`: concatenation only happens past preprocessing.
+    pub(super) fn check_concatenation(&mut self, tok: &PPToken) {
+        let TokenType::ATOM(Atom::STRING(encoding, _)) = tok.tokentype else { return; };
+        // The encoding of the string literals so far
+        let previous = self.output.iter().rev()
+            .map_while(|tok| match tok.tokentype {
+                TokenType::ATOM(Atom::STRING(encoding, _)) => Some(encoding),
+                _ => None
+            })
+            .try_fold(Encoding::Char, encoding_of);
+        if previous.is_some_and(|previous| encoding_of(previous, encoding).is_none()) {
+            let error = CompilerDiagnostic::Error(CompilerError::INCOMPATIBLE_STRING_CONCATENATION);
+            let diagnostic = self.diagnostic(error, tok.location, tok.expansion.clone());
+            self.unparsable.extend(diagnostic);
+        }
+    }
+}
@@ -0,0 +1,601 @@
+//! # Macros
+//! Macro definitions and the macro expansion algorithm.
+//!
+//! The expansion algorithm is the one described by Dave
+//! Prosser for the C89 committee: every token carries a
+//! hideset with the names of the macros it was expanded
+//! from, and a token is never expanded by a macro whose
+//! name is in its own hideset.
+//!
+//! The operands of `#` and `##` are the arguments as they were
+//! written, not their expansion. An empty argument next to a
+//! `##` is a "placemarker" (C99 6.10.3.3p2): pasting anything
+//! with it leaves the other operand unchanged.
+//!
+//! Variadic macros support two extensions on top of C99:
+//! GNU's `, ## __VA_ARGS__`, which drops the comma when there
+//! are no variadic arguments, and C23's `__VA_OPT__(tokens)`,
+//! which is replaced by `tokens` only when there are some.
+//!
+//! Every token resulting from an expansion remembers the macro
+//! it was expanded from, so that diagnostics can tell how they
+//! got there. `trace_expansions()` also records every step, for
+//! debugging.
+
+#![allow(clippy::needless_return)]
+
+use std::mem;
+use std::rc::Rc;
+
+use crate::front::lex::{Atom, Lexer, TokenType};
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::intern::Symbol;
+use crate::util::lang::{Extensions, Standard};
+use crate::util::scan::location::{Location, Span};
+use crate::util::scan::source::FileId;
+
+use super::builtin::Builtin;
+use super::token::{self, HideSet, PPToken};
+use super::Preprocessor;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroKind {
+    /// `#define NAME body`
+    Object,
+    /// `#define NAME(params) body`, where the last
+    /// parameter of a variadic macro is the variadic one
+    Function { params: Vec<Symbol>, variadic: bool },
+    /// A builtin macro like `__LINE__`, that
+    /// has no body to be expanded
+    Builtin(Builtin),
+}
+
+#[derive(Debug, Clone)]
+/// # Macro
+/// A macro definition, as created by `#define`.
+pub struct Macro {
+    pub name: Symbol,
+    pub kind: MacroKind,
+    pub body: Vec<PPToken>,
+    pub location: Location,
+    /// ## Origin
+    /// The file the macro is defined in, none for the
+    /// builtin and predefined macros.
+    pub origin: Option<Rc<Origin>>,
+    /// ## Builtin
+    /// Whether the macro is defined by the compiler, like
+    /// `__FILE__` or `__STDC_VERSION__`.
+    pub builtin: bool,
+}
+
+impl Macro {
+    /// # Same Definition
+    /// Two definitions of a macro are the same if they
+    /// have the same parameters and the same replacement
+    /// list, whitespace included (C99 6.10.3p2).
+    pub fn same_definition(&self, other: &Macro) -> bool {
+        return self.kind == other.kind
+            && self.body.len() == other.body.len()
+            && self.body.iter().zip(other.body.iter()).all(|(a, b)| {
+                a.spelling == b.spelling && a.space == b.space
+            });
+    }
+
+    /// # Validate
+    /// Checks the uses of `#`, `##` and `__VA_OPT__` in the body.
+    /// `##` needs an operand on both sides, while in function-like
+    /// macros `#` must be followed by a parameter or a `__VA_OPT__`.
+    pub fn validate(&self) -> Status {
+        let is_paste = |tok: Option<&PPToken>| matches!(tok, Some(tok) if tok.is(TokenType::HASHTWICE));
+        if is_paste(self.body.first()) || is_paste(self.body.last()) {
+            return error!(CompilerError::MISPLACED_PASTE);
+        }
+        let MacroKind::Function { .. } = self.kind else { return ok!(); };
+        for (i, tok) in self.body.iter().enumerate() {
+            if self.is_va_opt(tok) {
+                let Some(len) = self.va_opt_len(&self.body[i..]) else {
+                    return error!(CompilerError::MALFORMED_VA_OPT);
+                };
+                // '##' can't be at either end of __VA_OPT__ either
+                if is_paste(self.body.get(i + 2)) || is_paste(self.body.get(i + len - 2)) {
+                    return error!(CompilerError::MISPLACED_PASTE);
+                }
+            }
+            if tok.is(TokenType::HASH) {
+                match self.body.get(i + 1) {
+                    Some(next) if self.param(next).is_some() || self.is_va_opt(next) => (),
+                    _ => return error!(CompilerError::MISPLACED_STRINGIFY)
+                }
+            }
+        }
+        return ok!();
+    }
+
+    /// # Variadic
+    /// Returns the index of the variadic parameter, if any.
+    fn variadic(&self) -> Option<usize> {
+        match self.kind {
+            MacroKind::Function { ref params, variadic: true } => return Some(params.len() - 1),
+            _ => return None
+        }
+    }
+
+    fn is_va_opt(&self, tok: &PPToken) -> bool {
+        return self.variadic().is_some() && tok.ident() == Some("__VA_OPT__");
+    }
+
+    /// # Va Opt Len
+    /// Returns the number of tokens of the `__VA_OPT__(...)` at
+    /// the start of `body`, parentheses included.
+    fn va_opt_len(&self, body: &[PPToken]) -> Option<usize> {
+        if !body.get(1)?.is(TokenType::LEFT_PAREN) {
+            return None;
+        }
+        let mut depth = 0;
+        for (i, tok) in body.iter().enumerate().skip(1) {
+            match tok.tokentype {
+                TokenType::LEFT_PAREN => depth += 1,
+                TokenType::RIGHT_PAREN if depth == 1 => return Some(i + 1),
+                TokenType::RIGHT_PAREN => depth -= 1,
+                _ if self.is_va_opt(tok) => return None, // no nesting
+                _ => ()
+            }
+        }
+        return None;
+    }
+
+    fn param(&self, tok: &PPToken) -> Option<usize> {
+        let MacroKind::Function { ref params, .. } = self.kind else { return None; };
+        let name = tok.name()?;
+        return params.iter().position(|param| *param == name);
+    }
+}
+
+/// # Replacement
+/// The tokens part of a macro body is replaced by, and whether
+/// they start or end with a placemarker (C99 6.10.3.3p2), which
+/// only matters to `##`. Empty replacements, like an empty
+/// argument or a dropped `__VA_OPT__`, are a placemarker.
+struct Replacement {
+    tokens: Vec<PPToken>,
+    leading: bool,
+    trailing: bool,
+}
+
+impl Replacement {
+    fn new(tokens: Vec<PPToken>) -> Replacement {
+        let empty = tokens.is_empty();
+        return Replacement { tokens, leading: empty, trailing: empty };
+    }
+}
+
+fn hideset_union(a: &HideSet, b: &HideSet) -> HideSet {
+    let mut union = a.clone();
+    union.extend(b.iter().filter(|name| !a.contains(name)).cloned());
+    return union;
+}
+
+fn hideset_intersection(a: &HideSet, b: &HideSet) -> HideSet {
+    return a.iter().filter(|name| b.contains(name)).cloned().collect();
+}
+
+/// # Spell
+/// Returns the tokens as written, with a single space wherever
+/// there was whitespace between them.
+fn spell(tokens: &[PPToken]) -> String {
+    let mut text = String::new();
+    for (i, tok) in tokens.iter().enumerate() {
+        if i > 0 && tok.space {
+            text.push(' ');
+        }
+        text.push_str(&tok.spelling);
+    }
+    return text;
+}
+
+/// # Stringize
+/// Turns the tokens of an argument into a string literal, as
+/// done by `#`. Whitespace between tokens becomes a single
+/// space, and the quotes and backslashes of string and char
+/// literals are escaped (C99 6.10.3.2p2). Other tokens are
+/// kept as written, so a stray `\` stays a single one.
+fn stringize(arg: &[PPToken], location: Location) -> PPToken {
+    let mut text = String::new();
+    for (i, tok) in arg.iter().enumerate() {
+        if i > 0 && tok.space {
+            text.push(' ');
+        }
+        match tok.tokentype {
+            TokenType::ATOM(Atom::STRING(..) | Atom::CHAR(..)) => text.push_str(&token::escape(&tok.spelling)),
+            _ => text.push_str(&tok.spelling)
+        }
+    }
+    return PPToken::escaped(&text, location);
+}
+
+/// # Paste
+/// Pastes two tokens together, as done by `##`. The result is
+/// lexed again, and must be a single token. The new token keeps
+/// the position and hideset of the left one. Whether the result is
+/// a keyword depends on `standard` and `extensions`.
+fn paste(lhs: &PPToken, rhs: &PPToken, standard: Standard, extensions: Extensions) -> Option<PPToken> {
+    let spelling = format!("{}{}", lhs.spelling, rhs.spelling);
+    // Failures are reported as invalid pastes, not as lexer errors
+    let mut diag = Diagnostics::new();
+    let mut lexer = Lexer::new(spelling.as_bytes(), FileId::default(), &mut diag);
+    lexer.set_standard(standard);
+    lexer.set_extensions(extensions);
+    match token::from_tokens(spelling.as_bytes(), lexer, false).as_slice() {
+        [pasted, eof] if eof.is(TokenType::EOF) && *pasted.spelling == *spelling => {
+            return Some(PPToken {
+                location: lhs.location,
+                span: Span::at(lhs.location),
+                space: lhs.space,
+                bol: lhs.bol,
+                hideset: lhs.hideset.clone(),
+                ..pasted.clone()
+            });
+        },
+        _ => return None
+    }
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Expand Macro
+    /// Tries to expand `tok` as a macro invocation. On success the
+    /// expansion is pushed back onto the input, so that it gets
+    /// rescanned together with the rest of the source, and `true`
+    /// is returned.
+    pub(super) fn expand_macro(&mut self, tok: &PPToken) -> bool {
+        let Some(name) = tok.name() else { return false; };
+        if tok.hideset.contains(&name) {
+            return false;
+        }
+        let Some(mac) = self.macros.get(&name).cloned() else { return false; };
+
+        let args = match mac.kind {
+            MacroKind::Function { .. } => {
+                // A function-like macro name not followed by a '(' is just an identifier
+                if !matches!(self.input.last(), Some(next) if next.is(TokenType::LEFT_PAREN)) {
+                    return false;
+                }
+                self.input.pop();
+                let Some(args) = self.collect_args(&mac, tok) else { return true; };
+                Some(args)
+            },
+            _ => None
+        };
+        // From here on, `tok` stands for the invocation, and the
+        // diagnostics of the substitution come from inside `mac`
+        let expansion = Expansion {
+            name: mac.name,
+            location: mac.location,
+            origin: mac.origin.clone(),
+            parent: tok.expansion.clone(),
+        };
+        let tok = &PPToken { expansion: Some(Rc::new(expansion)), ..tok.clone() };
+
+        let step = self.trace(&mac, tok, args.as_ref().map(|(args, _)| args.as_slice()));
+        let (body, hideset) = match (&mac.kind, args) {
+            (MacroKind::Builtin(builtin), _) => {
+                (vec![self.expand_builtin(*builtin, tok)], hideset_union(&tok.hideset, &vec![mac.name]))
+            },
+            (_, Some((args, rparen))) => {
+                let hideset = hideset_intersection(&tok.hideset, &rparen.hideset);
+                (self.substitute(&mac, tok, args), hideset_union(&hideset, &vec![mac.name]))
+            },
+            (_, None) => {
+                (self.substitute(&mac, tok, vec![]), hideset_union(&tok.hideset, &vec![mac.name]))
+            }
+        };
+
+        if let (Some(trace), Some(step)) = (self.trace.as_mut(), step) {
+            trace[step].push_str(&format!(" -> {}", spell(&body)));
+        }
+        self.push_expansion(tok, body, &hideset);
+        return true;
+    }
+
+    /// # Trace Expansions
+    /// Starts recording every macro expansion, see `dump_expansions()`.
+    pub fn trace_expansions(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    /// # Dump Expansions
+    /// Returns the expansions recorded since `trace_expansions()`,
+    /// one per line, before their result is rescanned:
+    ///
+    /// ```text
+    /// main.c:3: MAX(a, b) -> ((a) > (b) ? (a) : (b))
+    /// ```
+    ///
+    /// Expansions happening during the rescan of another one are
+    /// indented below it.
+    pub fn dump_expansions(&self) -> String {
+        let mut dump = String::new();
+        for step in self.trace.iter().flatten() {
+            dump.push_str(step);
+            dump.push('\n');
+        }
+        return dump;
+    }
+
+    /// Records the invocation of `mac` by `tok`, if tracing, and
+    /// returns where, so that the result can be added later.
+    fn trace(&mut self, mac: &Macro, tok: &PPToken, args: Option<&[Vec<PPToken>]>) -> Option<usize> {
+        self.trace.as_ref()?;
+        let origin = self.includes.last().unwrap().origin.clone();
+        let mut step = format!("{}:{}: ", origin.file.display(), self.presumed_line(tok.location) + 1);
+        // The expansion of `tok` itself is the one of `mac`
+        let mut parent = &tok.expansion.as_ref().unwrap().parent;
+        while let Some(expansion) = parent {
+            step.push_str("  ");
+            parent = &expansion.parent;
+        }
+        step.push_str(&mac.name);
+        if let Some(args) = args {
+            let args: Vec<_> = args.iter().map(|arg| spell(arg)).collect();
+            step.push_str(&format!("({})", args.join(", ")));
+        }
+        let trace = self.trace.as_mut().unwrap();
+        trace.push(step);
+        return Some(trace.len() - 1);
+    }
+
+    /// # Collect Args
+    /// Reads the arguments of a function-like macro invocation,
+    /// the opening parenthesis excluded. Returns the arguments
+    /// and the closing parenthesis. The commas in the variadic
+    /// arguments are kept, as they all make up a single argument.
+    fn collect_args(&mut self, mac: &Macro, tok: &PPToken) -> Option<(Vec<Vec<PPToken>>, PPToken)> {
+        let mut args = vec![];
+        let mut arg = vec![];
+        let mut depth = 0;
+        let variadic = mac.variadic();
+        let rparen = loop {
+            let Some(mut t) = self.input.pop() else {
+                self.report_at(CompilerDiagnostic::Error(CompilerError::UNTERMINATED_MACRO_INVOCATION), tok);
+                return None;
+            };
+            // Arguments may span multiple lines, but they are not
+            // lines of their own anymore once substituted.
+            t.bol = false;
+            match t.tokentype {
+                TokenType::EOF => {
+                    self.input.push(t);
+                    self.report_at(CompilerDiagnostic::Error(CompilerError::UNTERMINATED_MACRO_INVOCATION), tok);
+                    return None;
+                },
+                TokenType::LEFT_PAREN => depth += 1,
+                TokenType::RIGHT_PAREN if depth == 0 => {
+                    args.push(arg);
+                    break t;
+                },
+                TokenType::RIGHT_PAREN => depth -= 1,
+                TokenType::COMMA if depth == 0 && Some(args.len()) != variadic => {
+                    args.push(mem::take(&mut arg));
+                    continue;
+                },
+                _ => ()
+            }
+            arg.push(t);
+        };
+
+        let MacroKind::Function { ref params, .. } = mac.kind else { unreachable!() };
+        // `F()` is an invocation with no arguments if F has no parameters
+        if params.is_empty() && args.len() == 1 && args[0].is_empty() {
+            args.clear();
+        }
+        // The variadic arguments can be left out entirely (C23, GNU)
+        if variadic.is_some() && args.len() == params.len() - 1 {
+            args.push(vec![]);
+        }
+        if args.len() != params.len() {
+            self.report_at(CompilerDiagnostic::Error(CompilerError::MACRO_ARGUMENT_MISMATCH), tok);
+            return None;
+        }
+        return Some((args, rparen));
+    }
+
+    /// # Substitute
+    /// Replaces the parameters in the body of a macro with its
+    /// fully expanded arguments, applying the `#` and `##`
+    /// operators along the way.
+    fn substitute(&mut self, mac: &Macro, tok: &PPToken, args: Vec<Vec<PPToken>>) -> Vec<PPToken> {
+        let mut expanded = vec![None; args.len()];
+        return self.replace(mac, &mac.body, tok, &args, &mut expanded).tokens;
+    }
+
+    /// # Replace
+    /// Does the actual work of `substitute` on `body`, which is
+    /// either the body of `mac` or the contents of one of its
+    /// `__VA_OPT__`s. `expanded` caches the expanded arguments.
+    fn replace(&mut self, mac: &Macro, body: &[PPToken], tok: &PPToken, args: &[Vec<PPToken>],
+        expanded: &mut [Option<Vec<PPToken>>]) -> Replacement {
+        let mut result: Vec<PPToken> = vec![];
+        // Whether the result starts with a placemarker, and whether
+        // it ends with one, which is the left operand of the next '##'
+        let mut leading = false;
+        let mut placemarker = false;
+        let mut i = 0;
+        while i < body.len() {
+            let t = &body[i];
+            if t.is(TokenType::HASHTWICE) {
+                let variadic = mac.variadic().is_some() && mac.param(&body[i + 1]) == mac.variadic();
+                let (rhs, len) = self.operand(mac, &body[i + 1..], tok, args, expanded);
+                i += 1 + len;
+                // GNU's `, ## __VA_ARGS__`: the comma is dropped if there
+                // are no variadic arguments, and nothing is pasted otherwise.
+                let comma = matches!(result.last(), Some(last) if last.is(TokenType::COMMA));
+                if variadic && comma && !placemarker {
+                    if rhs.tokens.is_empty() {
+                        result.pop();
+                    }
+                    result.extend(rhs.tokens);
+                    continue;
+                }
+                // Pasting a placemarker leaves the other operand as it is
+                if rhs.tokens.is_empty() {
+                    continue;
+                }
+                if placemarker || rhs.leading {
+                    if result.is_empty() {
+                        leading = rhs.leading;
+                    }
+                    placemarker = rhs.trailing;
+                    result.extend(rhs.tokens);
+                    continue;
+                }
+                let mut rhs_tokens = rhs.tokens.into_iter();
+                let first = rhs_tokens.next().unwrap();
+                // validate() makes sure '##' always has a left operand
+                let lhs = result.pop().unwrap();
+                match paste(&lhs, &first, self.standard, self.extensions) {
+                    Some(pasted) => result.push(pasted),
+                    None => {
+                        self.report_at(CompilerDiagnostic::Error(CompilerError::INVALID_PASTE), tok);
+                        result.extend([lhs, first]);
+                    }
+                }
+                result.extend(rhs_tokens);
+                placemarker = rhs.trailing;
+                continue;
+            }
+
+            let (operand, len) = self.operand(mac, &body[i..], tok, args, expanded);
+            i += len;
+            let pasted = matches!(body.get(i), Some(next) if next.is(TokenType::HASHTWICE));
+            let operand = match mac.param(t) {
+                Some(param) if !pasted => {
+                    let mut arg = self.expand_arg(param, args, expanded).to_vec();
+                    if let Some(first) = arg.first_mut() {
+                        first.space = t.space;
+                    }
+                    Replacement::new(arg)
+                },
+                _ => operand
+            };
+            if operand.tokens.is_empty() {
+                placemarker |= pasted;
+                continue;
+            }
+            if result.is_empty() {
+                leading = placemarker || operand.leading;
+            }
+            placemarker = operand.trailing;
+            result.extend(operand.tokens);
+        }
+        if result.is_empty() {
+            return Replacement::new(result);
+        }
+        return Replacement { tokens: result, leading, trailing: placemarker };
+    }
+
+    /// # Expand Arg
+    /// Returns the fully expanded argument of `param`. Each
+    /// argument is expanded at most once, no matter how many
+    /// times it appears in the body.
+    fn expand_arg<'a>(&mut self, param: usize, args: &[Vec<PPToken>], expanded: &'a mut [Option<Vec<PPToken>>]) -> &'a [PPToken] {
+        if expanded[param].is_none() {
+            expanded[param] = Some(self.expand_all(args[param].clone()));
+        }
+        return expanded[param].as_deref().unwrap();
+    }
+
+    /// # Operand
+    /// Returns the tokens the start of `body` stands for, and how
+    /// many body tokens were used: a parameter is its unexpanded
+    /// argument, `#param` its stringification, `__VA_OPT__(...)`
+    /// its replacement and anything else is just itself.
+    fn operand(&mut self, mac: &Macro, body: &[PPToken], tok: &PPToken, args: &[Vec<PPToken>],
+        expanded: &mut [Option<Vec<PPToken>>]) -> (Replacement, usize) {
+        let t = &body[0];
+        if let (MacroKind::Function { .. }, true) = (&mac.kind, t.is(TokenType::HASH)) {
+            // validate() makes sure '#' is always followed by a parameter or __VA_OPT__
+            let (operand, len) = match mac.param(&body[1]) {
+                Some(param) => (args[param].clone(), 1),
+                None => {
+                    let (replacement, len) = self.va_opt(mac, &body[1..], tok, args, expanded);
+                    (replacement.tokens, len)
+                }
+            };
+            let mut string = stringize(&operand, tok.location);
+            string.space = t.space;
+            return (Replacement::new(vec![string]), 1 + len);
+        }
+        if mac.is_va_opt(t) {
+            let (mut replacement, len) = self.va_opt(mac, body, tok, args, expanded);
+            if let Some(first) = replacement.tokens.first_mut() {
+                first.space = t.space;
+            }
+            return (replacement, len);
+        }
+        match mac.param(t) {
+            Some(param) => {
+                let mut arg = args[param].clone();
+                if let Some(first) = arg.first_mut() {
+                    first.space = t.space;
+                }
+                return (Replacement::new(arg), 1);
+            },
+            None => return (Replacement::new(vec![PPToken { location: tok.location, span: tok.span, ..t.clone() }]), 1)
+        }
+    }
+
+    /// # Va Opt
+    /// Replaces the `__VA_OPT__(...)` at the start of `body`. Its
+    /// contents are substituted like a body of their own if the
+    /// variadic arguments expand to some tokens, and dropped
+    /// otherwise (C23 6.10.5.2), so `__VA_OPT__` ignores an
+    /// argument that is just an empty macro. The placemarkers of
+    /// the contents are kept for `##`, like in `__VA_OPT__(a X ## X) ## b`.
+    fn va_opt(&mut self, mac: &Macro, body: &[PPToken], tok: &PPToken, args: &[Vec<PPToken>],
+        expanded: &mut [Option<Vec<PPToken>>]) -> (Replacement, usize) {
+        // validate() makes sure __VA_OPT__ is well formed
+        let len = mac.va_opt_len(body).unwrap();
+        if self.expand_arg(mac.variadic().unwrap(), args, expanded).is_empty() {
+            return (Replacement::new(vec![]), len);
+        }
+        return (self.replace(mac, &body[2..len - 1], tok, args, expanded), len);
+    }
+
+    /// # Expand All
+    /// Fully macro-expands a list of tokens in isolation from
+    /// the rest of the input, as needed for macro arguments.
+    pub(super) fn expand_all(&mut self, tokens: Vec<PPToken>) -> Vec<PPToken> {
+        let saved = mem::replace(&mut self.input, tokens.into_iter().rev().collect());
+        let mut expanded = vec![];
+        while let Some(t) = self.input.pop() {
+            if !self.expand_macro(&t) {
+                expanded.push(t);
+            }
+        }
+        self.input = saved;
+        return expanded;
+    }
+
+    /// # Push Expansion
+    /// Pushes the result of a macro expansion back onto the input.
+    /// The first token inherits the spacing of the macro name, so
+    /// that the output keeps the original layout.
+    fn push_expansion(&mut self, tok: &PPToken, mut body: Vec<PPToken>, hideset: &HideSet) {
+        for t in body.iter_mut() {
+            t.hideset = hideset_union(&t.hideset, hideset);
+            t.bol = false;
+            t.expansion = tok.expansion.clone();
+        }
+        match body.first_mut() {
+            Some(first) => {
+                first.space = tok.space;
+                first.bol = tok.bol;
+            },
+            // Empty expansion, the whitespace goes to the next token
+            None => if let Some(next) = self.input.last_mut() {
+                next.space |= tok.space;
+            }
+        }
+        self.input.extend(body.into_iter().rev());
+    }
+}
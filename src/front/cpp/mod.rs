@@ -1,2 +1,583 @@
 //! # Preprocessor
-//! Not implemented yet.
\ No newline at end of file
+//! This module contains the C preprocessor. It takes the
+//! tokens produced by the lexer, executes the directives
+//! and expands the macros, producing the token stream that
+//! is later fed to the parser.
+//!
+//! ## Supported features
+//! - [x] Object-like macros
+//! - [x] Function-like macros
+//! - [x] `#undef`
+//! - [x] `#include`, with `#pragma once` and include guards
+//! - [x] Conditional compilation
+//! - [x] Builtin macros (`__FILE__`, `__LINE__`, `__DATE__`...)
+//! - [x] Stringification and token pasting
+//! - [x] Variadic macros (`...`, GNU's `args...` and `, ## __VA_ARGS__`, C23's `__VA_OPT__`)
+//! - [x] `#line`, `#error` and `#warning`
+//! - [x] `#pragma` and `_Pragma`, see `register_pragma()`
+//! - [x] `#embed`
+//! - [x] Dependency files, see `depfile()`
+//! - [x] `-D`, `-U`, `-include` and `-imacros`, see `define_macro()`
+//!
+//! ## Implementation
+//! The tokens still to be preprocessed are kept in a stack
+//! (`input`, in reverse order), so that the result of a macro
+//! expansion can be pushed back onto it and rescanned together
+//! with the rest of the source, as the standard requires.
+
+#![allow(clippy::needless_return)]
+
+mod builtin;
+mod cmdline;
+mod cond;
+mod depend;
+mod embed;
+mod expr;
+mod include;
+mod literal;
+mod macros;
+mod pragma;
+mod print;
+mod token;
+pub use builtin::Builtin;
+pub use depend::Dependency;
+pub use include::SearchPaths;
+pub use macros::{Macro, MacroKind};
+pub use pragma::{Pragma, PragmaHandler};
+pub use token::{HideSet, PPToken};
+
+use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
+
+use crate::front::lex::{Atom, Token, TokenType};
+use cmdline::CommandLine;
+use cond::Conditional;
+use include::{Headers, Include};
+use pragma::DiagnosticState;
+use print::Linemarker;
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::diag::warn::CompilerWarning;
+use crate::util::escape::{self, Encoding};
+use crate::util::intern::Symbol;
+use crate::util::lang::{Extensions, Standard};
+use crate::util::scan::location::{Location, Span};
+use crate::util::scan::source::{FileId, SourceManager};
+
+/// # Preprocessor
+/// The `Preprocessor` struct contains the state of the
+/// preprocessor: the macro table, the tokens still to be
+/// processed and the expanded output.
+pub struct Preprocessor<'cpp> {
+    diag: &'cpp mut Diagnostics,
+    /// ## Sources
+    /// The files read so far, the included ones are
+    /// added as they are read.
+    sources: &'cpp mut SourceManager,
+    input: Vec<PPToken>,
+    /// ## Main
+    /// The main file and its tokens, only turned into `input`
+    /// by `preprocess()`, since their spelling depends on
+    /// `set_trigraphs()`.
+    main: (FileId, Vec<Token>),
+    macros: HashMap<Symbol, Rc<Macro>>,
+    output: Vec<PPToken>,
+    tokens: Vec<Token>,
+    /// ## Location
+    /// Location of the last token read by a directive,
+    /// used to report the diagnostics of directives.
+    location: Location,
+    /// ## Includes
+    /// The include stack. The last entry is the file being
+    /// preprocessed, the first one is the main source file.
+    includes: Vec<Include>,
+    /// ## Linemarkers
+    /// Where the output moves from a file to another, see `print()`.
+    linemarkers: Vec<Linemarker>,
+    paths: SearchPaths,
+    headers: Headers,
+    /// ## Dependencies
+    /// Every file opened so far, see `depfile()`.
+    dependencies: Vec<Dependency>,
+    pragma_handlers: HashMap<Rc<str>, PragmaHandler>,
+    /// ## Pragmas
+    /// The pragmas passed on to the later stages of the compiler.
+    pragmas: Vec<Pragma>,
+    /// ## Unparsable
+    /// The errors of the output that only matter past
+    /// preprocessing, like the ones of `OTHER` tokens and of
+    /// string literals that can't be concatenated. They are
+    /// only reported by `finish()`, so that `-E` works.
+    unparsable: Vec<Diagnostic>,
+    /// ## Lexed
+    /// The warnings of the lexer, held back until the tokens
+    /// they come before are reached, so that `-Wno-...` and
+    /// `#pragma GCC diagnostic` apply to them too.
+    lexed: Vec<Diagnostic>,
+    diagnostics: DiagnosticState,
+    /// ## Command Line
+    /// The `-D`, `-U`, `-include` and `-imacros` still to be
+    /// processed, see `define_macro()`.
+    command_line: CommandLine,
+    /// ## Conditionals
+    /// The stack of the `#if`s that are still open.
+    conds: Vec<Conditional>,
+    standard: Standard,
+    extensions: Extensions,
+    /// ## Trigraphs
+    /// Whether the included files are lexed with
+    /// trigraphs, see `Lexer::set_trigraphs()`.
+    trigraphs: bool,
+    /// ## Counter
+    /// The next value of `__COUNTER__`.
+    counter: usize,
+    /// ## Trace
+    /// The expansions done so far, when tracing them,
+    /// see `trace_expansions()`.
+    trace: Option<Vec<String>>,
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # New
+    /// A preprocessor for the `tokens` of `file`, which
+    /// must be in `sources`. The warnings of the lexer for
+    /// `file` that are in `diag` are taken back, to be
+    /// reported once the preprocessor reaches them.
+    pub fn new(file: FileId, tokens: Vec<Token>, sources: &'cpp mut SourceManager, diag: &'cpp mut Diagnostics) -> Self {
+        let lexed = diag.extract_if(.., |diagnostic| {
+            return diagnostic.location.file == file && matches!(diagnostic.diagnostic, CompilerDiagnostic::Warning(_));
+        }).collect();
+        let path = sources.path(file).to_path_buf();
+        Self {
+            diag,
+            sources,
+            input: vec![],
+            main: (file, tokens),
+            macros: HashMap::new(),
+            output: vec![],
+            tokens: vec![],
+            location: Location::new(),
+            includes: vec![Include {
+                path: path.clone(),
+                origin: Rc::new(Origin { file: path.clone(), included_from: None, delta: 0 }),
+                location: Location::new(),
+            }],
+            linemarkers: vec![],
+            paths: SearchPaths::default(),
+            headers: Headers::default(),
+            dependencies: vec![Dependency { path, system: false }],
+            pragma_handlers: pragma::default_handlers(),
+            pragmas: vec![],
+            unparsable: vec![],
+            lexed,
+            diagnostics: DiagnosticState::default(),
+            command_line: CommandLine::default(),
+            conds: vec![],
+            standard: Standard::default(),
+            extensions: Extensions::default(),
+            trigraphs: false,
+            counter: 0,
+            trace: None,
+        }
+    }
+
+    pub fn get_search_paths(&mut self) -> &mut SearchPaths {
+        return &mut self.paths;
+    }
+
+    pub fn set_standard(&mut self, standard: Standard) {
+        self.standard = standard;
+    }
+
+    /// # Set Extensions
+    /// Sets the extensions the included files are lexed
+    /// with, see `Lexer::set_extensions()`.
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.extensions = extensions;
+    }
+
+    pub fn set_trigraphs(&mut self, enabled: bool) {
+        self.trigraphs = enabled;
+    }
+
+    pub fn get_tokens(&mut self) -> &mut Vec<Token> {
+        return &mut self.tokens;
+    }
+
+    pub fn get_macro(&self, name: &str) -> Option<&Macro> {
+        return self.macros.get(&Symbol::intern(name)).map(|mac| &**mac);
+    }
+
+    /// # Next In Line
+    /// Returns the next token of the current line, or `None`
+    /// at the end of the line. Used to read directives.
+    fn next_in_line(&mut self) -> Option<PPToken> {
+        match self.input.last() {
+            Some(tok) if !tok.bol && !tok.is(TokenType::EOF) => {
+                let tok = self.input.pop().unwrap();
+                self.location = tok.location;
+                return Some(tok);
+            },
+            _ => return None
+        }
+    }
+
+    /// # Presumed Line
+    /// Returns the line of the current file that `#line`
+    /// makes `location` appear to be at, counted from 0.
+    fn presumed_line(&self, location: Location) -> usize {
+        return self.includes.last().unwrap().origin.line(self.sources, location);
+    }
+
+    /// # Report
+    /// Pushes a diagnostic, attaching the current include stack to it,
+    /// unless it is a disabled warning.
+    fn report(&mut self, diagnostic: CompilerDiagnostic, location: Location) {
+        self.report_expansion(diagnostic, location, None);
+    }
+
+    /// # Report At
+    /// Reports a diagnostic at `tok`, together with the chain of
+    /// macro expansions it comes from.
+    fn report_at(&mut self, diagnostic: CompilerDiagnostic, tok: &PPToken) {
+        self.report_expansion(diagnostic, tok.location, tok.expansion.clone());
+    }
+
+    fn report_expansion(&mut self, diagnostic: CompilerDiagnostic, location: Location, expansion: Option<Rc<Expansion>>) {
+        if let Some(diagnostic) = self.diagnostic(diagnostic, location, expansion) {
+            self.diag.push(diagnostic);
+        }
+    }
+
+    /// # Diagnostic
+    /// Makes the diagnostic `report_expansion()` pushes, if any.
+    fn diagnostic(&self, diagnostic: CompilerDiagnostic, location: Location, expansion: Option<Rc<Expansion>>) -> Option<Diagnostic> {
+        if let CompilerDiagnostic::Warning(ref warning) = diagnostic {
+            if self.diagnostics.is_ignored(warning) {
+                return None;
+            }
+        }
+        let origin = Some(self.includes.last().unwrap().origin.clone());
+        return Some(Diagnostic { diagnostic, location, origin, expansion });
+    }
+
+    /// # Reach
+    /// Reports the warnings of the lexer up to `location`,
+    /// unless they are ignored at this point.
+    fn reach(&mut self, location: Location) {
+        if self.lexed.is_empty() {
+            return;
+        }
+        let reached = self.lexed.extract_if(.., |diagnostic| {
+            return diagnostic.location.file == location.file && diagnostic.location.offset <= location.offset;
+        });
+        for diagnostic in reached {
+            match diagnostic.diagnostic {
+                CompilerDiagnostic::Warning(ref warning) if self.diagnostics.is_ignored(warning) => (),
+                _ => self.diag.push(diagnostic)
+            }
+        }
+    }
+
+    /// # Check Other
+    /// Records the error of `tok` if it is an `OTHER` token,
+    /// to be reported by `finish()`.
+    fn check_other(&mut self, tok: &PPToken) {
+        if !tok.is(TokenType::OTHER) {
+            return;
+        }
+        let error = match tok.spelling.chars().find(|c| matches!(c, '"' | '\'')) {
+            Some(quote) => CompilerError::UNTERMINATED_LITERAL(quote),
+            None => CompilerError::STRAY_CHARACTER(tok.spelling.chars().next().unwrap()),
+        };
+        let diagnostic = self.diagnostic(CompilerDiagnostic::Error(error), tok.location, tok.expansion.clone());
+        self.unparsable.extend(diagnostic);
+    }
+
+    fn skip_line(&mut self) {
+        while self.next_in_line().is_some() {}
+    }
+
+    fn directive(&mut self) -> Status {
+        let Some(name) = self.next_in_line() else {
+            return warning!(CompilerWarning::MISSING_PREPROCESSOR_DIRECTIVE);
+        };
+        match name.ident() {
+            Some("define")  => return self.define(),
+            Some("undef")   => return self.undef(),
+            Some("include") => return self.include(),
+            Some("embed")   => return self.embed(),
+            Some(directive @ ("if" | "ifdef" | "ifndef")) => return self.if_directive(directive),
+            Some(directive @ ("elif" | "elifdef" | "elifndef")) => return self.elif_directive(directive),
+            Some("else")    => return self.else_directive(),
+            Some("endif")   => return self.endif_directive(),
+            Some("line")    => return self.line_directive(false),
+            Some("error")   => return error!(CompilerError::ERROR_DIRECTIVE(self.message())),
+            Some("warning") => return warning!(CompilerWarning::WARNING_DIRECTIVE(self.message())),
+            Some("pragma")  => return self.pragma_directive(),
+            // GCC's linemarkers, as found in the output of `-E`: # 12 "file.h" 2
+            None if matches!(name.tokentype, TokenType::ATOM(Atom::NUM(_))) => {
+                self.input.push(name);
+                return self.line_directive(true);
+            },
+            _ => return error!(CompilerError::UNKNOWN_PREPROCESSOR_DIRECTIVE)
+        }
+    }
+
+    /// # Message
+    /// Returns the rest of the line as written, for `#error`
+    /// and `#warning`.
+    fn message(&mut self) -> String {
+        let mut message = String::new();
+        while let Some(tok) = self.next_in_line() {
+            if tok.space && !message.is_empty() {
+                message.push(' ');
+            }
+            message.push_str(&tok.spelling);
+        }
+        return message;
+    }
+
+    /// # Line Directive
+    /// `#line number "file"`: the next line becomes line `number`
+    /// of `file`, as far as diagnostics, `__LINE__` and `__FILE__`
+    /// are concerned. GCC's linemarkers can also have some flags
+    /// after the file name, which are ignored.
+    fn line_directive(&mut self, linemarker: bool) -> Status {
+        let physical = self.sources.line(self.location) + 1;
+        let mut line = vec![];
+        while let Some(tok) = self.next_in_line() {
+            line.push(tok);
+        }
+        let line = self.expand_all(line);
+        let (number, file) = match line.as_slice() {
+            [number] => (number, None),
+            [number, file, flags @ ..] if linemarker || flags.is_empty() => match file.tokentype {
+                TokenType::ATOM(Atom::STRING(Encoding::Char, ref file)) => {
+                    let file = escape::unescape(file, Encoding::Char, &mut vec![]);
+                    (number, Some(String::from_utf8_lossy(&escape::to_bytes(&file, Encoding::Char)).into_owned()))
+                },
+                _ => return error!(CompilerError::MALFORMED_LINE_DIRECTIVE)
+            },
+            _ => return error!(CompilerError::MALFORMED_LINE_DIRECTIVE)
+        };
+        // Only decimal digits, and GCC's linemarkers can start from 0
+        let min = if linemarker { 0 } else { 1 };
+        let number = match number.spelling.bytes().all(|c| c.is_ascii_digit()) {
+            true => number.spelling.parse::<isize>().ok().filter(|n| (min..=2147483647).contains(n)),
+            false => None
+        };
+        let Some(number) = number else {
+            return error!(CompilerError::MALFORMED_LINE_DIRECTIVE);
+        };
+
+        let include = self.includes.last_mut().unwrap();
+        // Lines are counted from 0
+        let delta = number - 1 - physical as isize;
+        let file = file.map_or_else(|| include.origin.file.clone(), Into::into);
+        let included_from = include.origin.included_from.clone();
+        include.origin = Rc::new(Origin { file, included_from, delta });
+        self.linemarkers.push(Linemarker {
+            index: self.output.len(),
+            origin: include.origin.clone(),
+            line: physical.saturating_add_signed(delta),
+            physical,
+            flag: None,
+        });
+        return ok!();
+    }
+
+    fn macro_name(&mut self) -> Result<Symbol, CompilerDiagnostic> {
+        let Some(tok) = self.next_in_line() else {
+            return Err(CompilerDiagnostic::Error(CompilerError::MISSING_MACRO_NAME));
+        };
+        match tok.ident() {
+            Some("defined") | None => return Err(CompilerDiagnostic::Error(CompilerError::INVALID_MACRO_NAME)),
+            Some(_) => return Ok(tok.spelling)
+        }
+    }
+
+    fn define(&mut self) -> Status {
+        let name = match self.macro_name() {
+            Ok(name) => name,
+            Err(err) => return Some(err)
+        };
+        let location = self.location;
+
+        // A '(' right after the name, without any whitespace in
+        // between, starts the parameter list of a function-like macro.
+        let kind = match self.input.last() {
+            Some(tok) if tok.is(TokenType::LEFT_PAREN) && !tok.space && !tok.bol => {
+                self.next_in_line();
+                match self.params() {
+                    Some(kind) => kind,
+                    None => return error!(CompilerError::MALFORMED_MACRO_PARAMETERS)
+                }
+            },
+            _ => MacroKind::Object
+        };
+
+        let mut body = vec![];
+        while let Some(tok) = self.next_in_line() {
+            body.push(tok);
+        }
+        if let Some(first) = body.first_mut() {
+            first.space = false;
+        }
+
+        let origin = Some(self.includes.last().unwrap().origin.clone());
+        let mac = Macro { name, kind, body, location, origin, builtin: false };
+        if let Some(err) = mac.validate() {
+            return Some(err);
+        }
+        let old = self.macros.insert(name, Rc::new(mac.clone()));
+        match old {
+            Some(old) if old.builtin => return warning!(CompilerWarning::UNDEFINED_BUILTIN_MACRO),
+            Some(old) if !old.same_definition(&mac) => return warning!(CompilerWarning::MACRO_REDEFINED),
+            _ => return ok!()
+        }
+    }
+
+    /// # Params
+    /// Parses the parameter list of a function-like macro,
+    /// the opening parenthesis excluded. Returns the names
+    /// of the parameters and whether the macro is variadic,
+    /// in which case the last parameter is the variadic one:
+    /// `__VA_ARGS__` for `...` and `args` for GNU's `args...`.
+    fn params(&mut self) -> Option<MacroKind> {
+        let mut params: Vec<Symbol> = vec![];
+        if matches!(self.input.last(), Some(tok) if tok.is(TokenType::RIGHT_PAREN) && !tok.bol) {
+            self.next_in_line();
+            return Some(MacroKind::Function { params, variadic: false });
+        }
+        loop {
+            let tok = self.next_in_line()?;
+            let variadic = tok.is(TokenType::TRIPLET);
+            let name = match variadic {
+                true => Symbol::intern("__VA_ARGS__"),
+                false => tok.name()?
+            };
+            if params.contains(&name) || (!variadic && *name == *"__VA_ARGS__") {
+                return None;
+            }
+            params.push(name);
+            match self.next_in_line()?.tokentype {
+                TokenType::COMMA if !variadic => continue,
+                TokenType::RIGHT_PAREN => return Some(MacroKind::Function { params, variadic }),
+                TokenType::TRIPLET if !variadic => match self.next_in_line()?.tokentype {
+                    TokenType::RIGHT_PAREN => return Some(MacroKind::Function { params, variadic: true }),
+                    _ => return None
+                },
+                _ => return None
+            }
+        }
+    }
+
+    fn undef(&mut self) -> Status {
+        let name = match self.macro_name() {
+            Ok(name) => name,
+            Err(err) => return Some(err)
+        };
+        match self.macros.remove(&name) {
+            Some(old) if old.builtin => return warning!(CompilerWarning::UNDEFINED_BUILTIN_MACRO),
+            _ => return ok!()
+        }
+    }
+
+    fn init(&mut self) {
+        while let Some(tok) = self.input.pop() {
+            self.reach(tok.location);
+            match tok.tokentype {
+                TokenType::EOF => {
+                    self.location = tok.location;
+                    self.unterminated_conditionals();
+                    // End of an included file, back to the includer
+                    if self.includes.len() > 1 {
+                        let include = self.includes.pop().unwrap();
+                        let physical = self.sources.line(include.location) + 1;
+                        let parent = self.includes.last().unwrap();
+                        self.linemarkers.push(Linemarker {
+                            index: self.output.len(),
+                            origin: parent.origin.clone(),
+                            line: physical.saturating_add_signed(parent.origin.delta),
+                            physical,
+                            flag: Some(2),
+                        });
+                        continue;
+                    }
+                    break;
+                },
+                // Tokens coming from a macro expansion never start a directive
+                TokenType::HASH if tok.bol && tok.hideset.is_empty() => {
+                    self.location = tok.location;
+                    if let Some(stat) = self.directive() {
+                        self.report(stat, self.location);
+                    }
+                    self.skip_line();
+                },
+                _ if self.expand_macro(&tok) => (),
+                _ if tok.ident() == Some("_Pragma") => {
+                    if let Some(stat) = self.pragma_operator(&tok) {
+                        self.report(stat, tok.location);
+                    }
+                },
+                _ => {
+                    self.check_literal(&tok);
+                    self.check_concatenation(&tok);
+                    self.check_other(&tok);
+                    self.output.push(tok);
+                }
+            }
+        }
+    }
+
+    /// # Finish
+    /// Converts the preprocessed tokens back into lexer
+    /// tokens for the parser, see `get_tokens()`. Directive
+    /// names outside of a directive are just identifiers, so
+    /// they are turned back into ones, and adjacent string
+    /// literals become a single one. The `OTHER` tokens and
+    /// the literals that can't be concatenated are reported.
+    pub fn finish(&mut self) {
+        self.diag.append(&mut self.unparsable);
+        let is_string = |tok: &PPToken| matches!(tok.tokentype, TokenType::ATOM(Atom::STRING(..)));
+        let mut i = 0;
+        while let Some(tok) = self.output.get(i) {
+            let len = self.output[i..].iter().take_while(|tok| is_string(tok)).count();
+            if len > 1 {
+                let (encoding, string) = literal::concatenate(&self.output[i..i + len]);
+                let tokentype = TokenType::ATOM(Atom::STRING(encoding, string.into()));
+                // The span covers every string, when they are in the same file
+                let last = self.output[i + len - 1].span;
+                let span = match last.file == tok.span.file && last.end >= tok.span.start {
+                    true => Span { end: last.end, ..tok.span },
+                    false => tok.span
+                };
+                self.tokens.push(Token { tokentype, location: tok.location, span, expansion: tok.expansion.clone(), space: tok.space, bol: tok.bol, trivia: None });
+                i += len;
+                continue;
+            }
+            i += 1;
+            let tokentype = match tok.tokentype {
+                TokenType::IDENTIFIER(_) | TokenType::INCLUDE | TokenType::PRAGMA
+                | TokenType::IFDEF | TokenType::DEFINE | TokenType::IFNDEF
+                | TokenType::ELIF | TokenType::ENDIF | TokenType::LINE
+                | TokenType::ERROR | TokenType::WARNING | TokenType::UNDEF
+                | TokenType::DEFINED => TokenType::IDENTIFIER(tok.spelling),
+                ref other => other.clone()
+            };
+            self.tokens.push(Token { tokentype, location: tok.location, span: tok.span, expansion: tok.expansion.clone(), space: tok.space, bol: tok.bol, trivia: None });
+        }
+        self.tokens.push(Token::new(TokenType::EOF, self.location));
+    }
+
+    pub fn preprocess(&mut self) {
+        let (file, tokens) = mem::take(&mut self.main);
+        self.input = token::from_tokens(self.sources.source(file), tokens, self.trigraphs);
+        self.input.reverse();
+        self.define_builtins();
+        self.process_command_line();
+        self.init();
+    }
+}
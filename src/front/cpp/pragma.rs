@@ -0,0 +1,306 @@
+//! # Pragmas
+//! `#pragma` and the `_Pragma("...")` operator.
+//!
+//! Pragmas are dispatched on their first token, the namespace
+//! (`GCC`, `STDC`, `pack`...), to the handler registered for it
+//! with `register_pragma()`. A handler can act on the pragma
+//! right away, like `#pragma once` does, and/or pass it on to
+//! the later stages of the compiler with `pass_pragma()`, like
+//! `#pragma pack` does. Passed on pragmas are also part of the
+//! output of `-E`.
+//!
+//! Unknown pragmas are ignored, with an `UNKNOWN_PRAGMA` warning
+//! that is disabled unless `-Wunknown-pragmas` is given.
+//!
+//! ## Supported pragmas
+//! - `#pragma once`
+//! - `#pragma GCC diagnostic push | pop | ignored | warning | error "-Wflag"`
+//! - `#pragma GCC warning | error "message"`
+//! - `#pragma pack(...)`
+//! - `#pragma weak symbol [= alias]`
+//! - `#pragma STDC FP_CONTRACT | FENV_ACCESS | CX_LIMITED_RANGE ON | OFF | DEFAULT`
+
+#![allow(clippy::needless_return)]
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::front::lex::{Atom, Lexer, TokenType};
+use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::diag::warn::CompilerWarning;
+use crate::util::escape::Encoding;
+use crate::util::scan::location::Location;
+use crate::util::scan::source::FileId;
+
+use super::token::{self, PPToken};
+use super::Preprocessor;
+
+/// # Pragma Handler
+/// Handles a pragma of the namespace it is registered for.
+pub type PragmaHandler = fn(&mut Preprocessor, Pragma) -> Status;
+
+#[derive(Debug, Clone)]
+/// # Pragma
+/// A `#pragma` directive or `_Pragma` operator.
+pub struct Pragma {
+    /// ## Tokens
+    /// The tokens after `#pragma`, namespace included.
+    /// They are not macro expanded.
+    pub tokens: Vec<PPToken>,
+    pub location: Location,
+    /// ## Index
+    /// The index of the output token the pragma comes before.
+    pub index: usize,
+    /// ## Markers
+    /// The number of linemarkers before the pragma, to tell
+    /// which ones come first when printing the output.
+    pub(super) markers: usize,
+}
+
+impl Pragma {
+    pub fn namespace(&self) -> Option<&str> {
+        return self.tokens.first()?.ident();
+    }
+}
+
+/// # Diagnostic State
+/// The warnings disabled by `-Wno-...` and `#pragma GCC diagnostic`,
+/// with a stack for `push` and `pop`.
+#[derive(Debug)]
+pub(super) struct DiagnosticState {
+    ignored: Vec<HashSet<Rc<str>>>,
+}
+
+impl Default for DiagnosticState {
+    fn default() -> Self {
+        return DiagnosticState { ignored: vec![HashSet::from(["unknown-pragmas".into()])] };
+    }
+}
+
+impl DiagnosticState {
+    pub fn is_ignored(&self, warning: &CompilerWarning) -> bool {
+        return warning.flag().is_some_and(|flag| self.ignored.last().unwrap().contains(flag));
+    }
+
+    pub fn set(&mut self, flag: &str, enabled: bool) {
+        let ignored = self.ignored.last_mut().unwrap();
+        match enabled {
+            true => ignored.remove(flag),
+            false => ignored.insert(flag.into())
+        };
+    }
+}
+
+/// The handlers registered by default.
+pub(super) fn default_handlers() -> HashMap<Rc<str>, PragmaHandler> {
+    let handlers: [(&str, PragmaHandler); 5] = [
+        ("once", once), ("GCC", gcc), ("pack", pack), ("weak", weak), ("STDC", stdc)
+    ];
+    return handlers.into_iter().map(|(namespace, handler)| (namespace.into(), handler)).collect();
+}
+
+fn once(cpp: &mut Preprocessor, _: Pragma) -> Status {
+    cpp.pragma_once();
+    return ok!();
+}
+
+fn gcc(cpp: &mut Preprocessor, pragma: Pragma) -> Status {
+    match pragma.tokens.get(1).and_then(PPToken::ident) {
+        Some("diagnostic") => (),
+        Some(kind @ ("warning" | "error")) => {
+            let [_, _, message] = pragma.tokens.as_slice() else {
+                return warning!(CompilerWarning::MALFORMED_PRAGMA);
+            };
+            let TokenType::ATOM(Atom::STRING(Encoding::Char, ref message)) = message.tokentype else {
+                return warning!(CompilerWarning::MALFORMED_PRAGMA);
+            };
+            match kind {
+                "error" => return error!(CompilerError::PRAGMA_ERROR(message.to_string())),
+                _ => return warning!(CompilerWarning::PRAGMA_WARNING(message.to_string()))
+            }
+        },
+        _ => return cpp.unknown_pragma(pragma)
+    }
+    let state = &mut cpp.diagnostics;
+    match &pragma.tokens[2..] {
+        [action] if action.ident() == Some("push") => {
+            let top = state.ignored.last().unwrap().clone();
+            state.ignored.push(top);
+        },
+        // A pop without a push is just ignored, like GCC does
+        [action] if action.ident() == Some("pop") => if state.ignored.len() > 1 {
+            state.ignored.pop();
+        },
+        [action, flag] => {
+            let enabled = match action.ident() {
+                Some("ignored") => false,
+                Some("warning" | "error") => true,
+                _ => return warning!(CompilerWarning::MALFORMED_PRAGMA)
+            };
+            let TokenType::ATOM(Atom::STRING(Encoding::Char, ref flag)) = flag.tokentype else {
+                return warning!(CompilerWarning::MALFORMED_PRAGMA);
+            };
+            let Some(flag) = flag.strip_prefix("-W") else {
+                return warning!(CompilerWarning::MALFORMED_PRAGMA);
+            };
+            state.set(flag, enabled);
+        },
+        _ => return warning!(CompilerWarning::MALFORMED_PRAGMA)
+    }
+    cpp.pass_pragma(pragma);
+    return ok!();
+}
+
+/// `#pragma pack()`, `pack(n)` and `pack(push|pop[, id][, n])`.
+fn pack(cpp: &mut Preprocessor, pragma: Pragma) -> Status {
+    let args = match &pragma.tokens[1..] {
+        [lparen, args @ .., rparen] if lparen.is(TokenType::LEFT_PAREN) && rparen.is(TokenType::RIGHT_PAREN) => args,
+        _ => return warning!(CompilerWarning::MALFORMED_PRAGMA)
+    };
+    let is_number = |tok: &PPToken| matches!(tok.tokentype, TokenType::ATOM(Atom::NUM(_)));
+    let valid = match args {
+        [] => true,
+        [n] => is_number(n),
+        [action, rest @ ..] => matches!(action.ident(), Some("push" | "pop"))
+            && rest.chunks(2).all(|arg| match arg {
+                [comma, arg] => comma.is(TokenType::COMMA) && (is_number(arg) || arg.ident().is_some()),
+                _ => false
+            })
+    };
+    if !valid {
+        return warning!(CompilerWarning::MALFORMED_PRAGMA);
+    }
+    cpp.pass_pragma(pragma);
+    return ok!();
+}
+
+/// `#pragma weak symbol` and `#pragma weak symbol = alias`.
+fn weak(cpp: &mut Preprocessor, pragma: Pragma) -> Status {
+    match &pragma.tokens[1..] {
+        [symbol] if symbol.ident().is_some() => (),
+        [symbol, eq, alias] if symbol.ident().is_some() && eq.is(TokenType::EQ) && alias.ident().is_some() => (),
+        _ => return warning!(CompilerWarning::MALFORMED_PRAGMA)
+    }
+    cpp.pass_pragma(pragma);
+    return ok!();
+}
+
+/// The standard pragmas (C99 6.10.6p2).
+fn stdc(cpp: &mut Preprocessor, pragma: Pragma) -> Status {
+    let words: Vec<_> = pragma.tokens[1..].iter().map(PPToken::ident).collect();
+    match words.as_slice() {
+        [Some("FP_CONTRACT" | "FENV_ACCESS" | "CX_LIMITED_RANGE"), Some("ON" | "OFF" | "DEFAULT")] => (),
+        [Some("FP_CONTRACT" | "FENV_ACCESS" | "CX_LIMITED_RANGE"), ..] => {
+            return warning!(CompilerWarning::MALFORMED_PRAGMA);
+        },
+        _ => return cpp.unknown_pragma(pragma)
+    }
+    cpp.pass_pragma(pragma);
+    return ok!();
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Register Pragma
+    /// Makes `handler` handle the pragmas of `namespace`,
+    /// replacing the previous handler, if any.
+    pub fn register_pragma(&mut self, namespace: &str, handler: PragmaHandler) {
+        self.pragma_handlers.insert(namespace.into(), handler);
+    }
+
+    /// # Get Pragmas
+    /// Returns the pragmas passed on by their handlers, in order.
+    pub fn get_pragmas(&self) -> &[Pragma] {
+        return &self.pragmas;
+    }
+
+    /// # Set Warning
+    /// Enables or disables the warnings of `flag`, given without
+    /// the `-W` (`-Wno-macro-redefined` is `("macro-redefined", false)`).
+    pub fn set_warning(&mut self, flag: &str, enabled: bool) {
+        self.diagnostics.set(flag, enabled);
+    }
+
+    /// # Pass Pragma
+    /// Passes `pragma` on to the later stages of the compiler.
+    pub fn pass_pragma(&mut self, pragma: Pragma) {
+        self.pragmas.push(pragma);
+    }
+
+    /// # Unknown Pragma
+    /// What happens to pragmas nobody knows about: they are
+    /// passed on, in case a later stage knows about them,
+    /// and warned about.
+    pub fn unknown_pragma(&mut self, pragma: Pragma) -> Status {
+        self.pass_pragma(pragma);
+        return warning!(CompilerWarning::UNKNOWN_PRAGMA);
+    }
+
+    /// # Pragma
+    /// Runs the handler of the pragma made of `tokens`.
+    pub(super) fn pragma(&mut self, tokens: Vec<PPToken>, location: Location) -> Status {
+        let pragma = Pragma { tokens, location, index: self.output.len(), markers: self.linemarkers.len() };
+        let handler = pragma.namespace().and_then(|namespace| self.pragma_handlers.get(namespace)).copied();
+        match handler {
+            Some(handler) => return handler(self, pragma),
+            // An empty pragma does nothing
+            None if pragma.tokens.is_empty() => return ok!(),
+            None => return self.unknown_pragma(pragma)
+        }
+    }
+
+    pub(super) fn pragma_directive(&mut self) -> Status {
+        let location = self.location;
+        let mut tokens = vec![];
+        while let Some(tok) = self.next_in_line() {
+            tokens.push(tok);
+        }
+        return self.pragma(tokens, location);
+    }
+
+    /// # Pragma Operator
+    /// `_Pragma("tokens")` is the same as `#pragma tokens` on a
+    /// line of its own (C99 6.10.9), but can be the result of a
+    /// macro expansion.
+    pub(super) fn pragma_operator(&mut self, tok: &PPToken) -> Status {
+        // The input is reversed: these are the next three tokens
+        let len = self.input.len();
+        let string = match self.input.get(len.saturating_sub(3)..) {
+            Some([rparen, string, lparen]) if lparen.is(TokenType::LEFT_PAREN) && rparen.is(TokenType::RIGHT_PAREN) => {
+                match string.tokentype {
+                    // C99 6.10.9: a string literal, or a wide one
+                    TokenType::ATOM(Atom::STRING(Encoding::Char | Encoding::Wide, string)) => string,
+                    _ => return error!(CompilerError::MALFORMED_PRAGMA_OPERATOR)
+                }
+            },
+            _ => return error!(CompilerError::MALFORMED_PRAGMA_OPERATOR)
+        };
+        self.input.truncate(len - 3);
+        // Destringizing only undoes the escaping of `"` and `\`
+        let mut destringized = String::with_capacity(string.len());
+        let mut chars = string.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('\\', Some('"' | '\\')) => destringized.push(chars.next().unwrap()),
+                _ => destringized.push(c)
+            }
+        }
+        let string = destringized;
+
+        let mut diag = Diagnostics::new();
+        let mut lexer = Lexer::new(string.as_bytes(), FileId::default(), &mut diag);
+        lexer.set_standard(self.standard);
+        lexer.set_extensions(self.extensions);
+        let mut tokens = token::from_tokens(string.as_bytes(), lexer, false);
+        tokens.pop(); // EOF
+        for t in tokens.iter_mut() {
+            t.location = tok.location;
+            t.span = tok.span;
+            t.bol = false;
+        }
+        if let Some(first) = tokens.first_mut() {
+            first.space = false;
+        }
+        return self.pragma(tokens, tok.location);
+    }
+}
@@ -0,0 +1,202 @@
+//! # Print
+//! Turns the preprocessed tokens back into C source, as
+//! done by `gcc -E`.
+//!
+//! The output keeps the line structure of the original files:
+//! tokens that started a line still do, and short runs of
+//! skipped lines (directives, comments, blank lines) are kept
+//! as blank lines. Longer runs, and every change of file, are
+//! replaced by a linemarker:
+//!
+//! ```text
+//! # 12 "file.h" 2
+//! ```
+//!
+//! meaning that the next line is line 12 of `file.h`. The
+//! flag is 1 when entering an included file, 2 when going back
+//! to the includer and missing otherwise. `#line` directives
+//! are turned into linemarkers too.
+//!
+//! The pragmas passed on by their handlers are printed as
+//! `#pragma` lines, even when they come from `_Pragma`, and the
+//! bytes of `#embed` as a list of integers.
+
+#![allow(clippy::needless_return)]
+
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::front::lex::{Lexer, TokenType};
+use crate::util::diag::*;
+use crate::util::scan::source::{FileId, SourceManager};
+
+use super::pragma::Pragma;
+use super::token::{self, PPToken};
+use super::Preprocessor;
+
+/// GCC's limit: more skipped lines than this are replaced by a linemarker.
+const MAX_BLANK_LINES: usize = 8;
+
+/// # Linemarker
+/// A change of the current file or line numbering in the
+/// output, right before the output token at `index`.
+pub(super) struct Linemarker {
+    pub index: usize,
+    pub origin: Rc<Origin>,
+    /// ## Line
+    /// The line the following tokens appear to start at,
+    /// after `#line`.
+    pub line: usize,
+    /// ## Physical
+    /// The line the following tokens actually start at.
+    pub physical: usize,
+    /// ## Flag
+    /// 1 when entering an included file, 2 when returning to
+    /// the includer, none after `#line`.
+    pub flag: Option<u8>,
+}
+
+/// # Would Paste
+/// Whether printing `tok` right after `prev` would lex as something
+/// else. Tokens that were next to each other in the source are fine,
+/// but macro expansions create new neighbours, even empty ones: with
+/// `#define E`, `+E+` must not be printed as `++`.
+fn would_paste(prev: &PPToken, tok: &PPToken) -> bool {
+    if prev.span.file == tok.span.file && prev.span.end == tok.span.start && prev.expansion.is_none() && tok.expansion.is_none() {
+        return false;
+    }
+    let spelling = format!("{}{}", prev.spelling, tok.spelling);
+    let mut diag = Diagnostics::new();
+    let lexer = Lexer::new(spelling.as_bytes(), FileId::default(), &mut diag);
+    match token::from_tokens(spelling.as_bytes(), lexer, false).as_slice() {
+        [a, b, eof] if eof.is(TokenType::EOF) => return a.spelling != prev.spelling || b.spelling != tok.spelling,
+        _ => return true
+    }
+}
+
+fn linemarker(text: &mut String, line: usize, file: &Path, flag: Option<u8>) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    let file = file.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+    // Lines are counted from 0
+    text.push_str(&format!("# {} \"{}\"", line + 1, file));
+    if let Some(flag) = flag {
+        text.push_str(&format!(" {flag}"));
+    }
+    text.push('\n');
+}
+
+/// # Printer
+/// The state of `print()`: the text so far, and the file and
+/// physical line its end is at.
+struct Printer<'a> {
+    sources: &'a SourceManager,
+    text: String,
+    linemarkers: bool,
+    file: &'a Path,
+    line: usize,
+    /// ## Delta
+    /// How much `#line` moved the lines of the file.
+    delta: isize,
+}
+
+impl<'a> Printer<'a> {
+    fn marker(&mut self, marker: &'a Linemarker) {
+        if self.linemarkers {
+            linemarker(&mut self.text, marker.line, &marker.origin.file, marker.flag);
+        }
+        self.file = &marker.origin.file;
+        self.line = marker.physical;
+        self.delta = marker.line as isize - marker.physical as isize;
+    }
+
+    /// # Start Line
+    /// Starts a new line of output for something on the physical
+    /// line `line`, keeping the lines in between, as blank lines
+    /// or with a linemarker.
+    fn start_line(&mut self, line: usize) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+            self.line += 1;
+        }
+        if self.linemarkers && (line < self.line || line - self.line > MAX_BLANK_LINES) {
+            linemarker(&mut self.text, line.saturating_add_signed(self.delta), self.file, None);
+        } else if self.linemarkers {
+            self.text.push_str(&"\n".repeat(line - self.line));
+        }
+        self.line = line;
+    }
+
+    /// Pragmas are printed as `#pragma` lines, `_Pragma` included.
+    fn pragma(&mut self, pragma: &Pragma) {
+        self.start_line(self.sources.line(pragma.location));
+        self.text.push_str("#pragma");
+        for (i, tok) in pragma.tokens.iter().enumerate() {
+            if i == 0 || tok.space {
+                self.text.push(' ');
+            }
+            self.text.push_str(&tok.spelling);
+        }
+        self.text.push('\n');
+        self.line += 1;
+    }
+}
+
+impl<'cpp> Preprocessor<'cpp> {
+    /// # Print
+    /// Returns the preprocessed source as text. Without `linemarkers`
+    /// (`-P`) the lines are kept, but not the blank ones, and nothing
+    /// tells where they come from.
+    pub fn print(&self, linemarkers: bool) -> String {
+        let file = self.includes[0].path.as_path();
+        let mut printer = Printer { sources: self.sources, text: String::new(), linemarkers, file, line: 0, delta: 0 };
+        if linemarkers {
+            linemarker(&mut printer.text, 0, file, None);
+        }
+
+        let mut markers = self.linemarkers.iter().peekable();
+        let mut pragmas = self.pragmas.iter().peekable();
+        let mut printed = 0;
+        let mut prev: Option<&PPToken> = None;
+        for i in 0..=self.output.len() {
+            let mut newline = false;
+            loop {
+                // A pragma goes first, unless some of the markers before it are still to be printed
+                if let Some(pragma) = pragmas.next_if(|pragma| pragma.index == i && pragma.markers <= printed) {
+                    printer.pragma(pragma);
+                    newline = true;
+                } else if let Some(marker) = markers.next_if(|marker| marker.index == i) {
+                    printer.marker(marker);
+                    printed += 1;
+                    newline = true;
+                } else {
+                    break;
+                }
+            }
+
+            let Some(tok) = self.output.get(i) else { break; };
+            if tok.bol || newline {
+                printer.start_line(self.sources.line(tok.location));
+            } else if let Some(prev) = prev {
+                // Two dots are fine, but a third one would make `...`
+                let ellipsis = *tok.spelling == *"." && printer.text.ends_with("..");
+                if tok.space || ellipsis || would_paste(prev, tok) {
+                    printer.text.push(' ');
+                }
+            }
+            match tok.tokentype {
+                TokenType::EMBED(ref bytes) => {
+                    let bytes: Vec<_> = bytes.iter().map(u8::to_string).collect();
+                    printer.text.push_str(&bytes.join(","));
+                },
+                _ => printer.text.push_str(&tok.spelling)
+            }
+            prev = Some(tok);
+        }
+        if !printer.text.is_empty() && !printer.text.ends_with('\n') {
+            printer.text.push('\n');
+        }
+        return printer.text;
+    }
+}
@@ -0,0 +1,150 @@
+#![allow(clippy::needless_return)]
+
+use std::rc::Rc;
+
+use crate::front::lex::{self, Atom, Token, TokenType};
+use crate::util::diag::Expansion;
+use crate::util::escape::Encoding;
+use crate::util::intern::Symbol;
+use crate::util::scan::location::{Location, Span};
+
+/// # HideSet
+/// The set of macro names a token has been produced
+/// by. A token whose hideset contains the name of a
+/// macro will never be expanded by that macro again,
+/// which is how recursive expansion is stopped (C99
+/// 6.10.3.4, also known as "painting a token blue").
+pub type HideSet = Vec<Symbol>;
+
+#[derive(Debug, Clone, PartialEq)]
+/// # PPToken
+/// A preprocessing token. It wraps the information
+/// of a lexer `Token` together with everything the
+/// preprocessor needs to know about it: its spelling,
+/// whether it is preceded by whitespace, whether it
+/// starts a line and which macros produced it.
+pub struct PPToken {
+    pub tokentype: TokenType,
+    pub spelling: Symbol,
+    pub location: Location,
+    /// ## Span
+    /// The source of the token, see `Token::span`.
+    /// Tokens coming from a macro expansion have the
+    /// span of the invocation.
+    pub span: Span,
+    /// ## Space
+    /// Whether the token is preceded by whitespace.
+    /// Needed to tell `#define F(x)` apart from
+    /// `#define F (x)`.
+    pub space: bool,
+    /// ## Beginning Of Line
+    /// Whether the token is the first one of its
+    /// line. Directives only start at the beginning
+    /// of a line.
+    pub bol: bool,
+    pub hideset: HideSet,
+    /// ## Expansion
+    /// The macro expansion the token comes from, if any.
+    pub expansion: Option<Rc<Expansion>>,
+}
+
+impl PPToken {
+    pub fn new(tokentype: TokenType, spelling: Symbol, location: Location) -> PPToken {
+        return PPToken { tokentype, spelling, location, span: Span::at(location), space: false, bol: false, hideset: vec![], expansion: None };
+    }
+
+    pub fn number(value: &str, location: Location) -> PPToken {
+        return PPToken::new(TokenType::ATOM(Atom::NUM(value.to_owned())), value.into(), location);
+    }
+
+    /// # String
+    /// Creates a string literal token containing `value`,
+    /// escaping its quotes and backslashes.
+    pub fn string(value: &str, location: Location) -> PPToken {
+        return PPToken::escaped(&escape(value), location);
+    }
+
+    /// # Escaped
+    /// Creates a string literal token whose contents are
+    /// `escaped`, which are already escaped as needed.
+    pub fn escaped(escaped: &str, location: Location) -> PPToken {
+        let spelling = format!("\"{escaped}\"");
+        return PPToken::new(TokenType::ATOM(Atom::STRING(Encoding::Char, escaped.into())), spelling.into(), location);
+    }
+
+    /// # Ident
+    /// Returns the name of the token if it can be used
+    /// as a macro name. In the preprocessor keywords are
+    /// just identifiers, so `#define inline` is valid, and
+    /// so are the ones with UCNs or Unicode, like `#define 日本`.
+    pub fn ident(&self) -> Option<&str> {
+        match &self.tokentype {
+            TokenType::IDENTIFIER(_) => return Some(&self.spelling),
+            keyword if keyword.is_keyword() => return Some(&self.spelling),
+            _ => return None
+        }
+    }
+
+    /// # Name
+    /// Same as `ident()`, as a `Symbol`, which is how
+    /// macro names are stored.
+    pub fn name(&self) -> Option<Symbol> {
+        return self.ident().map(|_| self.spelling);
+    }
+
+    pub fn is(&self, tokentype: TokenType) -> bool {
+        return self.tokentype == tokentype;
+    }
+}
+
+/// # Digraph
+/// Returns the digraph (C99 6.4.6) the punctuator `tok`
+/// is spelled with in `source`, if any. Digraphs behave
+/// like the punctuators they stand for, except when
+/// they are stringized.
+fn digraph(source: &[u8], tok: &Token) -> Option<&'static str> {
+    let digraph = match tok.tokentype {
+        TokenType::LEFT_BRACKET => "<:",
+        TokenType::RIGHT_BRACKET => ":>",
+        TokenType::LEFT_BRACE => "<%",
+        TokenType::RIGHT_BRACE => "%>",
+        TokenType::HASH => "%:",
+        TokenType::HASHTWICE => "%:%:",
+        _ => return None
+    };
+    return source.get(tok.location.offset..)?.starts_with(digraph.as_bytes()).then_some(digraph);
+}
+
+/// # From Tokens
+/// Converts the tokens produced by the lexer into
+/// preprocessing tokens, using `source` to recover
+/// the spelling of keywords, literals, digraphs and
+/// other tokens, replacing the trigraphs in them if
+/// they have been lexed with `trigraphs`.
+/// The tokens can come straight from a `Lexer`.
+pub fn from_tokens(source: &[u8], tokens: impl IntoIterator<Item = Token>, trigraphs: bool) -> Vec<PPToken> {
+    return tokens.into_iter().map(|tok| {
+        let written = || Symbol::intern(&String::from_utf8_lossy(&lex::unsplice(&source[tok.span.start..tok.span.end], trigraphs)));
+        let spelling: Symbol = match &tok.tokentype {
+            TokenType::IDENTIFIER(name) => *name,
+            TokenType::ATOM(Atom::NUM(spelling)) | TokenType::HEADER_NAME(spelling) => spelling.as_str().into(),
+            // Spelled as written, escapes included
+            TokenType::ATOM(Atom::STRING(..) | Atom::CHAR(..)) | TokenType::OTHER => written(),
+            // Keywords can have more spellings, like `bool` and `_Bool`
+            other if other.is_keyword() => written(),
+            other => digraph(source, &tok).or(other.spelling()).unwrap_or("").into(),
+        };
+        let mut pptok = PPToken::new(tok.tokentype, spelling, tok.location);
+        pptok.span = tok.span;
+        pptok.space = tok.space;
+        pptok.bol = tok.bol;
+        return pptok;
+    }).collect();
+}
+
+/// # Escape
+/// Escapes the quotes and backslashes of `value`, so that
+/// it can be written inside of a string literal.
+pub fn escape(value: &str) -> String {
+    return value.replace('\\', "\\\\").replace('"', "\\\"");
+}
@@ -8,316 +8,872 @@
 //! - [ ] K&R C (the one with the weird arguments)
 //! - [x] ANSI C / C89 / C90 (Work in progress)
 //! - [x] C99
-//! - [ ] C11
-//! - [ ] C17
-//! - [ ] C23
+//! - [x] C11
+//! - [x] C17
+//! - [x] C23
 //! - [ ] GCC Extensions (Work in progress, at least some of them)
-//! - [ ] MSVC Extensions
-//! - [ ] Plan9 Extensions
+//! - [ ] MSVC Extensions (Work in progress, at least some of them)
+//! - [ ] Plan9 Extensions (not planned for now)
+//! 
+//! The keywords depend on the standard and the extensions, see
+//! `Lexer::set_standard()` and `Lexer::set_extensions()`. Plan9's
+//! extensions, like unnamed struct members, add no keywords, so
+//! there is no Plan9 dialect.
 //! 
 //! Of course there is absolutely no guarantee that I will finish everything
 //! in this list.
 
+#![allow(clippy::needless_return)]
+
 mod token;
 pub use token::TokenType;
 pub use token::Token;
 pub use token::Atom;
+pub use token::Trivia;
 
 use core::str;
+use std::borrow::Cow;
+use std::mem;
 use std::vec;
 
 use crate::util::diag::*;
+use crate::util::diag::err::CompilerError;
+use crate::util::diag::warn::CompilerWarning;
+use crate::util::escape::{self, Encoding};
+use crate::util::intern::Symbol;
+use crate::util::lang::{Extensions, Standard};
+use crate::util::scan::location::{Location, Span};
+use crate::util::scan::source::FileId;
 use crate::util::scan::*;
 
 use phf::phf_map;
 
-static KEYWORDS: phf::Map<&'static [u8], TokenType> = phf_map! {
-    b"int"          => TokenType::INT,
-    b"long"         => TokenType::LONG,
-    b"float"        => TokenType::FLOAT,
-    b"double"       => TokenType::DOUBLE,
-    b"void"         => TokenType::VOID,
-    b"char"         => TokenType::CHAR,
-    b"short"        => TokenType::SHORT,
-    b"enum"         => TokenType::ENUM,
-    b"struct"       => TokenType::STRUCT,
-    b"union"        => TokenType::UNION,
-    b"_Bool"        => TokenType::BOOL,
-    b"_Complex"     => TokenType::COMPLEX,
-    b"_Imaginary"   => TokenType::IMAGINARY,
-    b"auto"         => TokenType::AUTO,
-    b"extern"       => TokenType::EXTERN,
-    b"register"     => TokenType::REGISTER,
-    b"static"       => TokenType::STATIC,
-    b"const"        => TokenType::CONST,
-    b"restrict"     => TokenType::RESTRICT,
-    b"volatile"     => TokenType::VOLATILE,
-    b"unsigned"     => TokenType::UNSIGNED,
-    b"signed"       => TokenType::SIGNED,
-    b"if"           => TokenType::IF,
-    b"else"         => TokenType::ELSE,
-    b"for"          => TokenType::FOR,
-    b"while"        => TokenType::WHILE,
-    b"break"        => TokenType::BREAK,
-    b"continue"     => TokenType::CONTINUE,
-    b"do"           => TokenType::DO,
-    b"goto"         => TokenType::GOTO,
-    b"switch"       => TokenType::SWITCH,
-    b"case"         => TokenType::CASE,
-    b"default"      => TokenType::DEFAULT,
-    b"return"       => TokenType::RETURN,
-    b"typedef"      => TokenType::TYPEDEF,
-    b"sizeof"       => TokenType::SIZEOF,
-    b"asm"          => TokenType::ASM,
+/// # Dialect
+/// Where a keyword comes from: the revision of the standard
+/// it was added in, or the extension adding it. Anywhere else
+/// it is just an identifier, see `Lexer::keyword()`.
+#[derive(Clone, Copy)]
+enum Dialect {
+    Since(Standard),
+    Gnu,
+    Msvc,
+}
+
+const C89: Dialect = Dialect::Since(Standard::C89);
+const C99: Dialect = Dialect::Since(Standard::C99);
+const C11: Dialect = Dialect::Since(Standard::C11);
+const C23: Dialect = Dialect::Since(Standard::C23);
+const GNU: Dialect = Dialect::Gnu;
+const MSVC: Dialect = Dialect::Msvc;
+
+static KEYWORDS: phf::Map<&'static [u8], (TokenType, Dialect)> = phf_map! {
+    b"int"          => (TokenType::INT, C89),
+    b"long"         => (TokenType::LONG, C89),
+    b"float"        => (TokenType::FLOAT, C89),
+    b"double"       => (TokenType::DOUBLE, C89),
+    b"void"         => (TokenType::VOID, C89),
+    b"char"         => (TokenType::CHAR, C89),
+    b"short"        => (TokenType::SHORT, C89),
+    b"enum"         => (TokenType::ENUM, C89),
+    b"struct"       => (TokenType::STRUCT, C89),
+    b"union"        => (TokenType::UNION, C89),
+    b"auto"         => (TokenType::AUTO, C89),
+    b"extern"       => (TokenType::EXTERN, C89),
+    b"register"     => (TokenType::REGISTER, C89),
+    b"static"       => (TokenType::STATIC, C89),
+    b"const"        => (TokenType::CONST, C89),
+    b"volatile"     => (TokenType::VOLATILE, C89),
+    b"unsigned"     => (TokenType::UNSIGNED, C89),
+    b"signed"       => (TokenType::SIGNED, C89),
+    b"if"           => (TokenType::IF, C89),
+    b"else"         => (TokenType::ELSE, C89),
+    b"for"          => (TokenType::FOR, C89),
+    b"while"        => (TokenType::WHILE, C89),
+    b"break"        => (TokenType::BREAK, C89),
+    b"continue"     => (TokenType::CONTINUE, C89),
+    b"do"           => (TokenType::DO, C89),
+    b"goto"         => (TokenType::GOTO, C89),
+    b"switch"       => (TokenType::SWITCH, C89),
+    b"case"         => (TokenType::CASE, C89),
+    b"default"      => (TokenType::DEFAULT, C89),
+    b"return"       => (TokenType::RETURN, C89),
+    b"typedef"      => (TokenType::TYPEDEF, C89),
+    b"sizeof"       => (TokenType::SIZEOF, C89),
+    b"__asm__"      => (TokenType::ASM, C89), // reserved, so always available
+
+    b"_Bool"        => (TokenType::BOOL, C99),
+    b"_Complex"     => (TokenType::COMPLEX, C99),
+    b"_Imaginary"   => (TokenType::IMAGINARY, C99),
+    b"restrict"     => (TokenType::RESTRICT, C99),
+    b"inline"       => (TokenType::INLINE, C99),
+    b"_Pragma"      => (TokenType::PRAGMA_OPERATOR, C99),
+
+    b"_Atomic"      => (TokenType::ATOMIC, C11),
+    b"_Alignas"     => (TokenType::ALIGNAS, C11),
+    b"_Alignof"     => (TokenType::ALIGNOF, C11),
+    b"_Generic"     => (TokenType::GENERIC, C11),
+    b"_Noreturn"    => (TokenType::NORETURN, C11),
+    b"_Static_assert" => (TokenType::STATIC_ASSERT, C11),
+    b"_Thread_local" => (TokenType::THREAD_LOCAL, C11),
+
+    b"bool"         => (TokenType::BOOL, C23),
+    b"true"         => (TokenType::TRUE, C23),
+    b"false"        => (TokenType::FALSE, C23),
+    b"nullptr"      => (TokenType::NULLPTR, C23),
+    b"constexpr"    => (TokenType::CONSTEXPR, C23),
+    b"typeof"       => (TokenType::TYPEOF, C23),
+    b"alignas"      => (TokenType::ALIGNAS, C23),
+    b"alignof"      => (TokenType::ALIGNOF, C23),
+    b"static_assert" => (TokenType::STATIC_ASSERT, C23),
+    b"thread_local" => (TokenType::THREAD_LOCAL, C23),
+
+    b"__attribute__" => (TokenType::ATTRIBUTE, GNU),
+    b"asm"          => (TokenType::ASM, GNU),
+    b"__typeof__"   => (TokenType::TYPEOF, GNU),
+    b"__extension__" => (TokenType::EXTENSION, GNU),
+    b"__inline__"   => (TokenType::INLINE, GNU),
+
+    b"__declspec"   => (TokenType::DECLSPEC, MSVC),
+    b"__int64"      => (TokenType::INT64, MSVC),
     // you can add any keyword here, as long there's a TokenType representing it
-    // b'_Atomic'   => TokenType::ATOMIC // for example
 
     // preprocessor
-    b"pragma"       => TokenType::PRAGMA,
-    b"include"      => TokenType::INCLUDE,
-    b"ifdef"        => TokenType::IFDEF,
-    b"define"       => TokenType::DEFINE,
-    b"ifndef"       => TokenType::IFNDEF,
-    b"elif"         => TokenType::ELIF,
-    b"endif"        => TokenType::ENDIF,
-    b"line"         => TokenType::LINE,
-    b"error"        => TokenType::ERROR,
-    b"warning"      => TokenType::WARNING, // C23
-    b"undef"        => TokenType::UNDEF,
-    b"defined"      => TokenType::DEFINED,
+    b"pragma"       => (TokenType::PRAGMA, C89),
+    b"include"      => (TokenType::INCLUDE, C89),
+    b"ifdef"        => (TokenType::IFDEF, C89),
+    b"define"       => (TokenType::DEFINE, C89),
+    b"ifndef"       => (TokenType::IFNDEF, C89),
+    b"elif"         => (TokenType::ELIF, C89),
+    b"endif"        => (TokenType::ENDIF, C89),
+    b"line"         => (TokenType::LINE, C89),
+    b"error"        => (TokenType::ERROR, C89),
+    b"warning"      => (TokenType::WARNING, C89), // C23, accepted by every standard
+    b"undef"        => (TokenType::UNDEF, C89),
+    b"defined"      => (TokenType::DEFINED, C89),
 };
 
+/// # Decode UTF-8
+/// Returns the character at the start of `bytes` and its
+/// length, if `bytes` starts with valid UTF-8.
+fn decode_utf8(bytes: &[u8]) -> Option<(char, usize)> {
+    let len = match bytes.first()? {
+        0x00 ..= 0x7F => 1,
+        0xC2 ..= 0xDF => 2,
+        0xE0 ..= 0xEF => 3,
+        0xF0 ..= 0xF4 => 4,
+        _ => return None
+    };
+    let c = str::from_utf8(bytes.get(..len)?).ok()?.chars().next()?;
+    return Some((c, len));
+}
+
+/// # Identifier Char
+/// Returns the length of the identifier character at the start
+/// of `bytes`, if any: a letter, an underscore, a digit (unless
+/// it is the `start` of the identifier), or any other character
+/// with the XID_Start or XID_Continue property, as is or as a
+/// universal character name.
+fn identifier_char(bytes: &[u8], start: bool) -> Option<usize> {
+    let (c, len) = match bytes {
+        [b'a' ..= b'z' | b'A' ..= b'Z' | b'_', ..] => return Some(1),
+        [b'0' ..= b'9', ..] if !start => return Some(1),
+        [b'\\', u @ (b'u' | b'U'), ..] => {
+            let len = if *u == b'u' { 4 } else { 8 };
+            let digits = str::from_utf8(bytes.get(2..2 + len)?).ok()?;
+            if !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let c = escape::universal_character(u32::from_str_radix(digits, 16).ok()?)?;
+            (c, 2 + len)
+        },
+        [0x80 ..= 0xFF, ..] => decode_utf8(bytes)?,
+        _ => return None
+    };
+    match start {
+        true if unicode_ident::is_xid_start(c) => return Some(len),
+        false if unicode_ident::is_xid_continue(c) => return Some(len),
+        _ => return None
+    }
+}
+
+/// # Trigraph
+/// Returns the character the trigraph `??c` stands
+/// for (C99 5.2.1.1), if any.
+fn trigraph(c: u8) -> Option<u8> {
+    match c {
+        b'=' => return Some(b'#'),
+        b'(' => return Some(b'['),
+        b'/' => return Some(b'\\'),
+        b')' => return Some(b']'),
+        b'\'' => return Some(b'^'),
+        b'<' => return Some(b'{'),
+        b'!' => return Some(b'|'),
+        b'>' => return Some(b'}'),
+        b'-' => return Some(b'~'),
+        _ => return None
+    }
+}
+
+/// # Unsplice
+/// Returns the spelling of a token from its `raw` source,
+/// removing the line splices it may be split by. With
+/// `trigraphs` (see `Lexer::set_trigraphs()`) they are
+/// replaced too, `??/` being a backslash, otherwise they
+/// are left as they are: `"??/"` is a complete string.
+pub fn unsplice(raw: &[u8], trigraphs: bool) -> Cow<'_, [u8]> {
+    if !raw.iter().any(|c| *c == b'\\' || (trigraphs && *c == b'?')) {
+        return Cow::Borrowed(raw);
+    }
+    let mut spelling = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let backslash = match &raw[i..] {
+            [b'\\', ..] => 1,
+            [b'?', b'?', b'/', ..] if trigraphs => 3,
+            [b'?', b'?', c, ..] if trigraphs && trigraph(*c).is_some() => {
+                spelling.extend(trigraph(*c));
+                i += 3;
+                continue;
+            },
+            [c, ..] => {
+                spelling.push(*c);
+                i += 1;
+                continue;
+            },
+            [] => break
+        };
+        i += backslash;
+        match &raw[i..] {
+            [b'\n', ..] => i += 1,
+            [b'\r', b'\n', ..] => i += 2,
+            _ => spelling.push(b'\\')
+        }
+    }
+    return Cow::Owned(spelling);
+}
+
+/// # Reconstruct
+/// Returns the source the `tokens` have been lexed
+/// from, if they have been lexed with their trivia
+/// (see `Lexer::set_trivia()`).
+pub fn reconstruct(tokens: &[Token]) -> Option<Vec<u8>> {
+    let mut source = vec![];
+    for tok in tokens {
+        let trivia = tok.trivia.as_ref()?;
+        source.extend_from_slice(&trivia.leading);
+        source.extend_from_slice(&trivia.text);
+    }
+    return Some(source);
+}
+
 /// # Lexer
 /// The `Lexer` struct contains most of the lexer
 /// implementation.
 /// 
-/// It consists of a scanner, a mutable reference
-/// to a diagnostics and a vector of lexed tokens
+/// It consists of a scanner and a mutable reference
+/// to a diagnostics. Tokens are lexed on demand, as
+/// the `Lexer` is an `Iterator` over them, ending
+/// with EOF. Diagnostics do not stop the lexing.
 /// 
-/// It currently accepts only ASCII source code
-/// and thus string literals, but UTF-8 support
-/// is planned.
-
-// TODO: impl Iterator for Lexer so that one can
-// easily iterate over each token by simply
-// calling .next() over the 
+/// The source code is UTF-8. Identifiers can be
+/// made of any XID_Start and XID_Continue character
+/// (C23 6.4.2.1), written as is or as a universal
+/// character name (`caf\u00E9`).
+/// 
+/// The first translation phases (C99 5.1.1.2) are
+/// done on the fly, see `peek()`: line splices are
+/// skipped wherever they are, even inside of tokens,
+/// and trigraphs are replaced if enabled. Tokens keep
+/// the location of their first character in the
+/// source and the span of their source, see
+/// `unsplice()`.
+/// 
+/// ## Example
+/// ```ignore
+/// let file = sources.add("main.c", "int x;");
+/// let tokens: Vec<Token> = Lexer::new(sources.source(file), file, &mut diag).collect();
+/// ```
 pub struct Lexer<'lex> {
     diag: &'lex mut Diagnostics,
     scan: Scanner<'lex, u8, 3>, // TODO: Check if I can lower the lookahead by writing some tests.
-    tokens: Vec<Token>,
+    /// ## Trigraphs
+    /// Whether trigraphs are replaced, see `set_trigraphs()`.
+    trigraphs: bool,
+    /// ## Standard
+    /// The standard whose keywords are lexed, see `set_standard()`.
+    standard: Standard,
+    extensions: Extensions,
+    /// ## Trivia
+    /// Where the trivia of the next token starts, if the
+    /// trivia are kept, see `set_trivia()`.
+    trivia: Option<usize>,
+    /// ## Space
+    /// Whether whitespace or a comment has been skipped
+    /// since the last token.
+    space: bool,
+    /// ## Beginning Of Line
+    /// Whether a newline has been skipped since the last
+    /// token. Spliced lines and comments spanning more
+    /// lines do not count.
+    bol: bool,
+    /// ## Hash
+    /// Whether the last token is a `#`, which may start
+    /// an `#include`.
+    hash: bool,
+    /// ## Header
+    /// Whether a header name may follow, since the last
+    /// token is the `include` or `embed` of a directive.
+    header: bool,
+    /// ## EOF
+    /// Whether the EOF token has been returned, ending
+    /// the iteration.
+    eof: bool,
 }
 
 impl<'lex> Lexer<'lex> {
-    pub fn new(source: &'lex [u8], diag: &'lex mut Diagnostics) -> Self {
+    /// # New
+    /// A lexer over `source`, which is the source of
+    /// `file` in the `SourceManager`.
+    pub fn new(source: &'lex [u8], file: FileId, diag: &'lex mut Diagnostics) -> Self {
+        let mut scan = Scanner::new(source);
+        scan.location.file = file;
         Self {
-            scan: Scanner::new(source, Some(b'\n')),
-            tokens: vec![],
-            diag
+            scan,
+            diag,
+            trigraphs: false,
+            standard: Standard::default(),
+            extensions: Extensions::default(),
+            trivia: None,
+            space: false,
+            bol: true,
+            hash: false,
+            header: false,
+            eof: false,
         }
     }
 
-    // &mut so we can later change it (??)
-    pub fn get_tokens(&mut self) -> &mut Vec<Token> {
-        return &mut self.tokens;
+    /// # Set Trigraphs
+    /// Enables or disables the replacement of trigraphs
+    /// (`-trigraphs`). When disabled, trigraphs are left
+    /// as they are and reported.
+    pub fn set_trigraphs(&mut self, enabled: bool) {
+        self.trigraphs = enabled;
+    }
+
+    /// # Set Standard
+    /// Selects the standard (`-std=`) whose keywords are
+    /// lexed as such: in C89 `inline` and `restrict` are
+    /// just identifiers.
+    pub fn set_standard(&mut self, standard: Standard) {
+        self.standard = standard;
+    }
+
+    /// # Set Extensions
+    /// Enables the keywords of the extensions, like GNU's
+    /// `__attribute__` or MSVC's `__declspec`.
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.extensions = extensions;
+    }
+
+    /// # Set Trivia
+    /// Makes the lexer keep the trivia of every token (see
+    /// `Trivia`), so that the source can be reconstructed
+    /// from the tokens. Meant for tools working on the
+    /// source as it is written, like formatters: directive
+    /// lines are kept as trivia instead of being lexed, and
+    /// the tokens can't be preprocessed.
+    pub fn set_trivia(&mut self, enabled: bool) {
+        self.trivia = enabled.then_some(self.scan.location.offset);
+    }
+
+    // The length of the line splice (a backslash followed by a
+    // newline) at `pos`, if any.
+    fn splice(&self, pos: usize) -> Option<usize> {
+        let source = self.scan.item_collection.get(pos..)?;
+        let backslash = match source {
+            [b'\\', ..] => 1,
+            [b'?', b'?', b'/', ..] if self.trigraphs => 3,
+            _ => return None
+        };
+        match &source[backslash..] {
+            [b'\n', ..] => return Some(backslash + 1),
+            [b'\r', b'\n', ..] => return Some(backslash + 2),
+            _ => return None
+        }
+    }
+
+    // Translation phases 1 and 2: returns the character at `pos`,
+    // after skipping line splices and replacing trigraphs, together
+    // with the position right after it.
+    fn translate(&self, mut pos: usize) -> Option<(u8, usize)> {
+        while let Some(len) = self.splice(pos) {
+            pos += len;
+        }
+        let source = self.scan.item_collection;
+        let replaced = source.get(pos..pos + 3)
+            .filter(|_| self.trigraphs)
+            .and_then(|chars| match chars {
+                [b'?', b'?', c] => trigraph(*c),
+                _ => None
+            });
+        if let Some(c) = replaced {
+            return Some((c, pos + 3));
+        }
+        return source.get(pos).map(|c| (*c, pos + 1));
+    }
+
+    /// # Peek
+    /// Returns the `n`th next character, as seen after the
+    /// first two translation phases.
+    fn peek(&self, n: usize) -> Option<u8> {
+        let mut pos = self.scan.location.offset;
+        for _ in 0..n {
+            pos = self.translate(pos)?.1;
+        }
+        return self.translate(pos).map(|(c, _)| c);
+    }
+
+    // The next `n` characters, see `peek()`.
+    fn lookahead(&self, n: usize) -> Vec<u8> {
+        let mut chars = Vec::with_capacity(n);
+        let mut pos = self.scan.location.offset;
+        while let (true, Some((c, next))) = (chars.len() < n, self.translate(pos)) {
+            chars.push(c);
+            pos = next;
+        }
+        return chars;
+    }
+
+    fn skip_splices(&mut self) {
+        while let Some(len) = self.splice(self.scan.location.offset) {
+            self.scan.nth(len - 1);
+        }
+    }
+
+    /// # Advance
+    /// Moves past the next `n` characters, see `peek()`.
+    fn advance(&mut self, n: usize) {
+        self.skip(n, true);
+    }
+
+    // Same as `advance()`, but when trigraphs are disabled only
+    // the ones that would splice a line are reported, since the
+    // others can't change the meaning of a comment.
+    fn skip(&mut self, n: usize, warn: bool) {
+        for _ in 0..n {
+            self.skip_splices();
+            let pos = self.scan.location.offset;
+            let Some((_, next)) = self.translate(pos) else {
+                return;
+            };
+            if let (false, Some([b'?', b'?', c])) = (self.trigraphs, self.scan.item_collection.get(pos..pos + 3)) {
+                let rest = self.scan.item_collection.get(pos + 3..);
+                let splice = *c == b'/' && matches!(rest, Some([b'\n', ..] | [b'\r', b'\n', ..]));
+                if trigraph(*c).is_some() && (warn || splice) {
+                    let warning = CompilerWarning::TRIGRAPH_IGNORED(*c as char);
+                    self.diag.push(Diagnostic::new(CompilerDiagnostic::Warning(warning), self.scan.location));
+                }
+            }
+            self.scan.nth(next - pos - 1);
+        }
     }
 
-    fn emit_token(&mut self, tok: TokenType) -> Status {
-        self.tokens.push(Token::new(tok, self.scan.location.clone()));
-        // push the pointer
-        self.scan.next();
-        return self.init();
+    // Makes a token starting at `location`, that has just been
+    // skipped.
+    fn emit(&mut self, tokentype: TokenType, location: Location) -> Token {
+        self.hash = tokentype == TokenType::HASH;
+        let mut token = Token::new(tokentype, location);
+        token.span = Span { end: self.scan.location.offset, ..token.span };
+        token.space = mem::take(&mut self.space);
+        token.bol = mem::take(&mut self.bol);
+        if let Some(start) = self.trivia {
+            let source = self.scan.item_collection;
+            let end = self.scan.location.offset;
+            token.trivia = Some(Box::new(Trivia {
+                leading: source[start..location.offset].to_vec(),
+                text: source[location.offset..end].to_vec(),
+            }));
+            self.trivia = Some(end);
+        }
+        return token;
     }
 
-    fn emit_token_double(&mut self, tok: TokenType) -> Status {
-        self.emit_token(tok);
-        self.scan.nth(2);
-        return self.init();
+    // Skips a directive line, keeping it as trivia. Comments
+    // and literals are skipped as a whole, since they may
+    // contain anything.
+    fn directive_trivia(&mut self) {
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(b'\n') | None, _) => return,
+                (Some(b'/'), Some(b'/')) => return self.comment(),
+                (Some(b'/'), Some(b'*')) => self.multiline_comment(),
+                (Some(quote @ (b'"' | b'\'')), _) => {
+                    self.skip(1, false);
+                    while !matches!(self.peek(0), Some(b'\n') | None) {
+                        let c = self.peek(0);
+                        self.skip(1 + usize::from(c == Some(b'\\') && self.peek(1) != Some(b'\n')), false);
+                        if c == Some(quote) {
+                            break;
+                        }
+                    }
+                },
+                _ => self.skip(1, false)
+            }
+        }
     }
 
-    fn ignore_line(&mut self) -> Status {
-        while self.scan.peek(1) != Some(&b'\n') {
-            self.scan.next();
+    fn ignore_line(&mut self) {
+        while !matches!(self.peek(0), Some(b'\n') | None) {
+            self.skip(1, false);
         }
-        self.scan.next();
-        return self.init();
     }
 
     // FIXME: Very inefficient I am using .peek() and other weird tricks everywhere! 
-    fn ident_or_keyword(&mut self) -> Status {
-        // Clear the buffer BEFORE acting on it
-        self.scan.reset_buffer();
-
-        // Start pushing first character into self.buffer.
-        // I've done This because an identifier can contain
-        // a number, just not at the start of the identifier
-        // itself. As such we first match for an identifier
-        // without numbers, so only a..=z, A..=Z and _, we
-        // push that first character and then we match the
-        // actual rest of the identifier, which can indeed
-        // contain other stuff like numbers.
-        let Some(_) = self.scan.peek(0) else {
-            self.tokens.push(Token::new(TokenType::EOF, self.scan.location));
-            return ok!();
-        };
-        //self.scan.push_to_buffer(); // ?
-
-        // Push the next character into self.buffer until it isn't part of an
-        // identifier anymore, so if the next char isn't any of the
-        // following (a...z, A...Z, _, 0...9) characters.
-        while matches!(self.scan.peek(1), Some(b'a' ..= b'z'| b'A' ..= b'Z' | b'0' ..= b'9' | b'_')) {
-            self.scan.push_to_buffer();
-            self.scan.next();
+    fn ident_or_keyword(&mut self) -> Token {
+        let location = self.scan.location;
+
+        // Skip the characters until they aren't part of an identifier
+        // anymore, see `identifier_char()`. The longest one is a
+        // `\UXXXXXXXX`.
+        // The first character has already been matched by `token()`, so
+        // it cannot be a number.
+        while let Some(len) = identifier_char(&self.lookahead(10), false) {
+            self.advance(len);
         }
 
-        // If we cannot get anything from the buffer it means that we reached eof?
-        let Some(ref buf) = self.scan.get_from_buffer() else {
-            self.tokens.push(Token::new(TokenType::EOF, self.scan.location));
-            return ok!();
-        };
+        let source = self.scan.item_collection;
+        let spelling = unsplice(&source[location.offset..self.scan.location.offset], self.trigraphs);
+
+        // `L"..."`, `u8'...'` and so on are literals, not identifiers
+        if let (Some(encoding), Some(b'"' | b'\'')) = (Encoding::from_prefix(&spelling), self.peek(0)) {
+            return self.literal(location, encoding);
+        }
 
         // Check if the current identifier is actually a keyword or, well, just an identifier.
-        let ident = TokenType::IDENTIFIER(self.scan.buffer.clone().unwrap()); // TODO: Remove this .clone()
-        let kw = KEYWORDS.get(buf).unwrap_or(&ident);
-        
-        self.scan.buffer.as_mut().unwrap();
-        return self.emit_token(kw.to_owned());
+        let kw = match self.keyword(&spelling) {
+            Some(kw) => kw,
+            None => TokenType::IDENTIFIER(escape::unescape_identifier(&String::from_utf8_lossy(&spelling)).into()),
+        };
+
+        // `#include` and `#embed` are followed by a header name, which is
+        // not lexed like the rest of the source code.
+        self.header = self.hash && (kw == TokenType::INCLUDE || &*spelling == b"embed");
+        return self.emit(kw, location);
     }
 
-    // Just adds the number to the buffer without parsing it
-    fn number(&mut self) -> Status {
-        self.scan.reset_buffer();
-        while matches!(self.scan.peek(0), Some(b'0' ..= b'9' | b'\'' | b'b' | b'o' | b'x' | b'p' | b'e')) {
-            self.scan.push_to_buffer();
-            self.scan.next();
+    // The keyword spelled `spelling`, if it is one in the
+    // selected standard and extensions.
+    fn keyword(&self, spelling: &[u8]) -> Option<TokenType> {
+        let (kw, dialect) = KEYWORDS.get(spelling)?;
+        let enabled = match dialect {
+            Dialect::Since(standard) => self.standard >= *standard,
+            Dialect::Gnu => self.extensions.gnu,
+            Dialect::Msvc => self.extensions.msvc,
+        };
+        return enabled.then(|| kw.clone());
+    }
+
+    // Lexes `<file.h>` or `"file.h"` as a single token. Backslashes and
+    // quotes have no special meaning inside of a header name. If the
+    // name is not closed on the same line it is lexed as usual, and the
+    // preprocessor will complain about it.
+    fn header_name(&mut self) -> Option<Token> {
+        while matches!(self.peek(0), Some(b' ' | b'\t')) {
+            self.space = true;
+            self.advance(1);
         }
-        let num = str::from_utf8(self.scan.get_from_buffer().unwrap())
-            .expect("Invalid utf-8!")
-            .to_owned();
-        return self.emit_token(TokenType::ATOM(Atom::NUM(num)));
+        self.skip_splices();
+        let close = match self.peek(0)? {
+            b'<' => b'>',
+            b'"' => b'"',
+            _ => return None
+        };
+        let location = self.scan.location;
+        let mut name = vec![];
+        let mut pos = location.offset;
+        loop {
+            match self.translate(pos) {
+                Some((b'\n', _)) | None => return None,
+                Some((c, next)) => {
+                    name.push(c);
+                    pos = next;
+                    if name.len() > 1 && c == close {
+                        break;
+                    }
+                }
+            }
+        }
+        self.advance(name.len());
+        return Some(self.emit(TokenType::HEADER_NAME(String::from_utf8_lossy(&name).into_owned()), location));
+    }
+
+    // Just adds the number to the buffer without parsing it.
+    // This follows the definition of a "preprocessing number" (C99 6.4.8),
+    // so suffixes, hexadecimal digits and exponent signs are all kept, and
+    // the actual validation is left to the `NumberParser`.
+    fn number(&mut self) -> Token {
+        let location = self.scan.location;
+        let mut num = vec![];
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(c @ (b'e' | b'E' | b'p' | b'P')), Some(sign @ (b'+' | b'-'))) => {
+                    num.extend([c, sign]);
+                    self.advance(2);
+                },
+                (Some(c @ (b'0' ..= b'9' | b'a' ..= b'z' | b'A' ..= b'Z' | b'_' | b'.' | b'\'')), _) => {
+                    num.push(c);
+                    self.advance(1);
+                },
+                _ => break
+            }
+        }
+        let num = String::from_utf8(num).expect("Invalid utf-8!");
+        return self.emit(TokenType::ATOM(Atom::NUM(num)), location);
     }
 
 
-    fn comment(&mut self) -> Status {
-        let mut cur = self.scan.peek(0);
-        while cur != Some(&b'\n') {
-            // If cur is None, we reached EOF, so we terminate the lexing stage.
-            if cur == None {
-                return ok!();
+    // Lexes a string literal or a character constant, keeping its
+    // contents as they are written: escape sequences are only checked
+    // and replaced once the literal is used (see `util::escape`), so
+    // that the preprocessor can still spell it. A literal that is not
+    // closed on its line is an `OTHER` token up to the end of the line,
+    // which is only an error if it is not skipped by the preprocessor,
+    // like in `#if 0` or `#error don't`.
+    // `location` is where the literal starts, prefix included.
+    fn literal(&mut self, location: Location, encoding: Encoding) -> Token {
+        let quote = self.peek(0).unwrap();
+        self.advance(1);
+        let mut contents = vec![];
+        let terminated = loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(c), _) if c == quote => break true,
+                (Some(b'\\'), Some(c)) if c != b'\n' => {
+                    contents.extend([b'\\', c]);
+                    self.advance(2);
+                },
+                (Some(b'\n') | None, _) => break false,
+                (Some(c), _) => {
+                    contents.push(c);
+                    self.advance(1);
+                }
             }
-            self.scan.next();
-            cur = self.scan.peek(0);
+        };
+        if !terminated {
+            return self.emit(TokenType::OTHER, location);
         }
-        return self.init();
+        self.advance(1);
+        if str::from_utf8(&contents).is_err() {
+            self.diag.push(Diagnostic::new(CompilerDiagnostic::Error(CompilerError::INVALID_UTF8), location));
+        }
+        let contents = String::from_utf8_lossy(&contents);
+        let atom = match quote {
+            b'"' => Atom::STRING(encoding, Symbol::intern(&contents)),
+            _ => Atom::CHAR(encoding, contents.into_owned())
+        };
+        return self.emit(TokenType::ATOM(atom), location);
     }
 
-    fn multiline_comment(&mut self) -> Status {
-        todo!("TBD");
+    // Any other character: identifiers can start with some of them,
+    // the others are each an `OTHER` token (C99 6.4p1), like `@` or a
+    // lone `\`, which can still be stringized. Invalid UTF-8 is
+    // reported and skipped, and the lexing goes on anyway.
+    fn other_character(&mut self) -> Option<Token> {
+        let location = self.scan.location;
+        let chars = self.lookahead(10);
+        if identifier_char(&chars, true).is_some() {
+            return Some(self.ident_or_keyword());
+        }
+        if let Some((_, len)) = decode_utf8(&chars) {
+            self.advance(len);
+            return Some(self.emit(TokenType::OTHER, location));
+        }
+        let error = CompilerError::INVALID_UTF8;
+        self.diag.push(Diagnostic::new(CompilerDiagnostic::Error(error), location));
+        self.advance(1);
+        return None;
     }
 
-    fn init(&mut self) -> Status {
-        let Some(current) = self.scan.peek(0) else {
-            // Push EOF
-            self.tokens.push(Token::new(TokenType::EOF, self.scan.location));
-            return ok!();
-        };
+    // A `//` comment goes on after a line splice.
+    fn comment(&mut self) {
+        while !matches!(self.peek(0), Some(b'\n') | None) {
+            self.skip(1, false);
+        }
+    }
 
-        match (*current, self.scan.peek(0)) {
-                // Shebang, we ignore it, maybe we shouldn't
-            (b'#', Some(b'!')) => return self.ignore_line(),
-            (b'#', Some(b'#')) => return self.emit_token(TokenType::HASHTWICE),
-            (b'#', _) => return self.emit_token(TokenType::HASH),
-            (b'a' ..= b'z' | b'A' ..= b'Z' | b'_', _) => return self.ident_or_keyword(),
-            (b'0' ..= b'9', _) => return self.number(),
-            (b'(', _) => return self.emit_token(TokenType::LEFT_PAREN),
-            (b'[', _) => return self.emit_token(TokenType::LEFT_BRACKET),
-            (b'{', _) => return self.emit_token(TokenType::LEFT_BRACE),
-            (b')', _) => return self.emit_token(TokenType::RIGHT_PAREN),
-            (b']', _) => return self.emit_token(TokenType::RIGHT_BRACKET),
-            (b'}', _) => return self.emit_token(TokenType::RIGHT_BRACE),
-
-            (b';', _) => return self.emit_token(TokenType::SEMICOLON),
-            (b':', _) => return self.emit_token(TokenType::COLON),
-            (b',', _) => return self.emit_token(TokenType::COMMA),
-            (b'?', _) => return self.emit_token(TokenType::QUESTION),
-
-            // TODO: add self.peek_double() and self.emit_token_triple()
-            (b'.', Some(b'.')) => match self.scan.peek(2) { // A bit messy I'm sorry
-                Some(b'.') => {
-                    self.scan.nth(3);
-                    return self.emit_token(TokenType::TRIPLET);
+    // An unterminated comment is reported and goes on until EOF.
+    fn multiline_comment(&mut self) {
+        let location = self.scan.location;
+        self.skip(2, false);
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(b'*'), Some(b'/')) => return self.skip(2, false),
+                (None, _) => {
+                    let error = CompilerError::UNTERMINATED_COMMENT;
+                    self.diag.push(Diagnostic::new(CompilerDiagnostic::Error(error), location));
+                    return;
+                },
+                _ => self.skip(1, false)
+            }
+        }
+    }
+
+    /// # Token
+    /// Lexes the next token, skipping whitespace and comments.
+    /// Comments are replaced by a space (C99 5.1.1.2), so newlines
+    /// inside of a `/* */` comment do not start a new line.
+    fn token(&mut self) -> Token {
+        if mem::take(&mut self.header) {
+            if let Some(header) = self.header_name() {
+                return header;
+            }
+        }
+        loop {
+            self.skip_splices();
+            let location = self.scan.location;
+            let Some(current) = self.peek(0) else {
+                self.eof = true;
+                return self.emit(TokenType::EOF, location);
+            };
+            if self.trivia.is_some() && self.bol && matches!((current, self.peek(1)), (b'#', _) | (b'%', Some(b':'))) {
+                self.directive_trivia();
+                continue;
+            }
+
+            let (tokentype, len) = match (current, self.peek(1)) {
+                    // Shebang, we ignore it, maybe we shouldn't
+                (b'#', Some(b'!')) => {
+                    self.ignore_line();
+                    continue;
+                },
+                (b'#', Some(b'#')) => (TokenType::HASHTWICE, 2),
+                (b'#', _) => (TokenType::HASH, 1),
+                (b'a' ..= b'z' | b'A' ..= b'Z' | b'_', _) => return self.ident_or_keyword(),
+                (b'0' ..= b'9', _) => return self.number(),
+                (b'"' | b'\'', _) => return self.literal(location, Encoding::Char),
+
+                // digraphs (C99 6.4.6)
+                (b'<', Some(b':')) => (TokenType::LEFT_BRACKET, 2),
+                (b':', Some(b'>')) => (TokenType::RIGHT_BRACKET, 2),
+                (b'<', Some(b'%')) => (TokenType::LEFT_BRACE, 2),
+                (b'%', Some(b'>')) => (TokenType::RIGHT_BRACE, 2),
+                (b'%', Some(b':')) => match (self.peek(2), self.peek(3)) {
+                    (Some(b'%'), Some(b':')) => (TokenType::HASHTWICE, 4),
+                    _ => (TokenType::HASH, 2)
                 },
-                _ => return self.emit_token(TokenType::DOT)
-            },
 
-            (b'.', _) =>  return self.emit_token(TokenType::TRIPLET),
+                (b'(', _) => (TokenType::LEFT_PAREN, 1),
+                (b'[', _) => (TokenType::LEFT_BRACKET, 1),
+                (b'{', _) => (TokenType::LEFT_BRACE, 1),
+                (b')', _) => (TokenType::RIGHT_PAREN, 1),
+                (b']', _) => (TokenType::RIGHT_BRACKET, 1),
+                (b'}', _) => (TokenType::RIGHT_BRACE, 1),
+
+                (b';', _) => (TokenType::SEMICOLON, 1),
+                // Before C23, `a::b` is `a : : b`, like in bit-fields and labels
+                (b':', Some(b':')) if self.standard >= Standard::C23 => (TokenType::DOUBLECOLON, 2),
+                (b':', _) => (TokenType::COLON, 1),
+                (b',', _) => (TokenType::COMMA, 1),
+                (b'?', _) => (TokenType::QUESTION, 1),
+
+                (b'.', Some(b'.')) => match self.peek(2) {
+                    Some(b'.') => (TokenType::TRIPLET, 3),
+                    _ => (TokenType::DOT, 1)
+                },
+                (b'.', Some(b'0' ..= b'9')) => return self.number(), // .5
+                (b'.', _) => (TokenType::DOT, 1),
+
+                // operators
+                (b'+', Some(b'=')) => (TokenType::PLUSEQ, 2),
+                (b'+', Some(b'+')) => (TokenType::PLUSPLUS, 2),
+                (b'+', _)          => (TokenType::PLUS, 1),
+
+                (b'-', Some(b'=')) => (TokenType::MINUSEQ, 2),
+                (b'-', Some(b'-')) => (TokenType::MINUSMINUS, 2),
+                (b'-', Some(b'>')) => (TokenType::ARROW, 2),
+                (b'-', _)          => (TokenType::MINUS, 1),
+
+                (b'*', Some(b'=')) => (TokenType::ASTERISKEQ, 2),
+                (b'*', _)          => (TokenType::ASTERISK, 1),
+
+                (b'/', Some(b'/')) => {
+                    self.comment();
+                    self.space = true;
+                    continue;
+                },
+                (b'/', Some(b'*')) => {
+                    self.multiline_comment();
+                    self.space = true;
+                    continue;
+                },
+                (b'/', Some(b'=')) => (TokenType::SLASHEQ, 2),
+                (b'/', _)          => (TokenType::SLASH, 1),
 
-            // operators
-            (b'+', Some(b'=')) => return self.emit_token_double(TokenType::PLUSEQ),
-            (b'+', Some(b'+')) => return self.emit_token_double(TokenType::PLUSPLUS),
-            (b'+', _)          => return self.emit_token(TokenType::PLUS),
+                (b'%', Some(b'=')) => (TokenType::PERCENTEQ, 2),
+                (b'%', _)          => (TokenType::PERCENT, 1),
 
-            (b'-', Some(b'=')) => return self.emit_token_double(TokenType::MINUSEQ),
-            (b'-', Some(b'-')) => return self.emit_token_double(TokenType::MINUSMINUS),
-            (b'-', Some(b'>')) => return self.emit_token_double(TokenType::ARROW),
-            (b'-', _)          => return self.emit_token(TokenType::MINUS),
+                (b'=', Some(b'=')) => (TokenType::DOUBLEEQ, 2),
+                (b'=', _)          => (TokenType::EQ, 1),
 
-            (b'*', Some(b'=')) => return self.emit_token_double(TokenType::ASTERISKEQ),
-            (b'*', _)          => return self.emit_token(TokenType::ASTERISK),
+                (b'!', Some(b'=')) => (TokenType::NEQ, 2),
+                (b'!', _)          => (TokenType::BANG, 1),
 
-            (b'/', Some(b'/')) => self.comment(),
-            (b'/', Some(b'*')) => self.multiline_comment(),
-            (b'/', Some(b'=')) => return self.emit_token_double(TokenType::SLASHEQ),
-            (b'/', _)          => return self.emit_token(TokenType::SLASH),
+                (b'~', _)          => (TokenType::TILDE, 1),
 
-            (b'=', Some(b'=')) => return self.emit_token_double(TokenType::EQ),
-            (b'=', _)          => return self.emit_token(TokenType::DOUBLEEQ),
+                (b'&', Some(b'&')) => (TokenType::DOUBLEAMPERSAND, 2),
+                (b'&', Some(b'=')) => (TokenType::AMPERSANDEQ, 2),
+                (b'&', _)          => (TokenType::AMPERSAND, 1),
 
-            (b'!', Some(b'=')) => return self.emit_token_double(TokenType::NEQ),
-            (b'!', _)          => return self.emit_token(TokenType::BANG),
+                (b'|', Some(b'|')) => (TokenType::DOUBLEBAR, 2),
+                (b'|', Some(b'=')) => (TokenType::BAREQ, 2),
+                (b'|', _)          => (TokenType::BAR, 1),
 
-            (b'~', _)          => return self.emit_token(TokenType::TILDE),
+                (b'^', Some(b'=')) => (TokenType::CARETEQ, 2),
+                (b'^', _)          => (TokenType::CARET, 1),
 
-            // TODO: add self.peek_double() and self.emit_token_triple() here too
-            (b'<', Some(b'=')) => return self.emit_token_double(TokenType::LESSEQ),
-            (b'<', Some(b'<')) => match self.scan.peek(2) {
-                Some(b'=') => {
-                    self.scan.nth(3);
-                    return self.emit_token(TokenType::DOUBLELESSEQ);
+                (b'<', Some(b'=')) => (TokenType::LESSEQ, 2),
+                (b'<', Some(b'<')) => match self.peek(2) {
+                    Some(b'=') => (TokenType::DOUBLELESSEQ, 3),
+                    _ => (TokenType::DOUBLELESS, 2),
                 },
-                _ => return self.emit_token_double(TokenType::DOUBLELESS),
-            },
-            (b'<', _) => return self.emit_token(TokenType::LESS),
+                (b'<', _) => (TokenType::LESS, 1),
 
-            (b'>', Some(b'=')) => return self.emit_token_double(TokenType::GREATEREQ),
-            (b'>', Some(b'>')) => match self.scan.peek(2) {
-                Some(b'=') => {
-                    self.scan.nth(3);
-                    return self.emit_token(TokenType::DOUBLEGREATEREQ);
+                (b'>', Some(b'=')) => (TokenType::GREATEREQ, 2),
+                (b'>', Some(b'>')) => match self.peek(2) {
+                    Some(b'=') => (TokenType::DOUBLEGREATEREQ, 3),
+                    _ => (TokenType::DOUBLEGREATER, 2),
                 },
-                _ => return self.emit_token_double(TokenType::DOUBLEGREATER),
-            },
-            (b'>', _) => return self.emit_token(TokenType::GREATER),
+                (b'>', _) => (TokenType::GREATER, 1),
 
-            (b' ' | b'\t' | b'\n' | b'\r', _) => {
-                self.scan.next();
-                return self.init();
-            }
+                (b'\n', _) => {
+                    self.bol = true;
+                    self.space = true;
+                    self.advance(1);
+                    continue;
+                },
+                (b' ' | b'\t' | b'\r' | b'\x0b' | b'\x0c', _) => {
+                    self.space = true;
+                    self.advance(1);
+                    continue;
+                },
 
-            _ => ok!() // NO UTF-8 Support for now
+                _ => match self.other_character() {
+                    Some(tok) => return tok,
+                    None => continue
+                }
+            };
+            self.advance(len);
+            return self.emit(tokentype, location);
         }
     }
+}
 
-    pub fn lex(&mut self) {
-        match self.init() {
-            Some(ref stat) => {
-                self.diag.push(Diagnostic { diagnostic: stat.clone(), location: self.scan.location });
-                match stat {
-                    CompilerDiagnostic::Error(_) => (),
-                    CompilerDiagnostic::Warning(_) => return self.lex(), // continue like nothing happened.
-                }
-            }
-            
-            None => ()
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.eof {
+            return None;
         }
+        return Some(self.token());
     }
 }
@@ -1,18 +1,67 @@
-use crate::util::scan::location::Location;
-use crate::util::scan::buffer::Buffer;
+#![allow(clippy::needless_return)]
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::util::diag::Expansion;
+use crate::util::escape::Encoding;
+use crate::util::intern::Symbol;
+
+use crate::util::scan::location::{Location, Span};
 
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub struct Token {
     pub tokentype: TokenType,
-    pub location: Location
+    pub location: Location,
+    /// ## Span
+    /// The source of the token, from its first byte
+    /// up to the one after its last, line splices
+    /// included. Empty for tokens that are not in the
+    /// source as they are, like the pasted ones.
+    pub span: Span,
+    /// ## Expansion
+    /// The macro expansion the token comes from, if any.
+    pub expansion: Option<Rc<Expansion>>,
+    /// ## Space
+    /// Whether the token is preceded by whitespace
+    /// or a comment.
+    pub space: bool,
+    /// ## Beginning Of Line
+    /// Whether the token is the first one of its
+    /// line, spliced lines being a single line.
+    pub bol: bool,
+    /// ## Trivia
+    /// The source around the token, only kept when
+    /// asked for, see `Lexer::set_trivia()`.
+    pub trivia: Option<Box<Trivia>>,
 }
 
 impl Token {
     pub fn new(tokentype: TokenType, location: Location) -> Token {
-        return Token { tokentype, location }
+        return Token { tokentype, location, span: Span::at(location), expansion: None, space: false, bol: false, trivia: None }
     }
 }
 
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+/// # Trivia
+/// Everything in the source that comes before a
+/// token without being part of any: whitespace,
+/// comments, line splices and whole directive lines,
+/// together with the token exactly as it is written.
+/// Joining the two for every token, EOF included,
+/// gives back the source file byte for byte, see
+/// `reconstruct()`.
+pub struct Trivia {
+    /// ## Leading
+    /// The source between the previous token and
+    /// this one.
+    pub leading: Vec<u8>,
+    /// ## Text
+    /// The source of the token, with its line splices
+    /// and trigraphs.
+    pub text: Vec<u8>,
+}
+
 
 #[allow(non_camel_case_types)]
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
@@ -25,10 +74,13 @@ pub enum TokenType {
     AUTO, EXTERN, REGISTER, STATIC,
 
     // Type qualifiers
-    CONST, RESTRICT, VOLATILE,
+    CONST, RESTRICT, VOLATILE, ATOMIC,
 
     // Type Modifiers
     UNSIGNED, SIGNED,
+
+    // Function specifiers
+    INLINE, NORETURN,
     
     // Control flow
     IF, ELSE, FOR, WHILE, BREAK, CONTINUE, DO, GOTO,
@@ -36,6 +88,17 @@ pub enum TokenType {
 
     // Misc
     TYPEDEF, SIZEOF, ASM, // Inline assembler
+    ALIGNAS, ALIGNOF, GENERIC, STATIC_ASSERT, THREAD_LOCAL,
+    PRAGMA_OPERATOR, // _Pragma
+
+    // C23
+    TRUE, FALSE, NULLPTR, CONSTEXPR, TYPEOF,
+
+    // GNU extensions
+    ATTRIBUTE, EXTENSION,
+
+    // MSVC extensions
+    DECLSPEC, INT64,
 
     // Punctuation, operators and symbols
 //  +     -      *         /      !     %
@@ -58,13 +121,25 @@ pub enum TokenType {
     DOUBLEGREATEREQ, DOUBLELESSEQ, LEFT_PAREN, RIGHT_PAREN,
 //  [             ]              {           }
     LEFT_BRACKET, RIGHT_BRACKET, LEFT_BRACE, RIGHT_BRACE,
+//  :: (C23)
+    DOUBLECOLON,
 
     // Preprocessor (warning -> C23)
     INCLUDE, PRAGMA, IFDEF, DEFINE, IFNDEF, ELIF, ENDIF,
     LINE, ERROR, WARNING, UNDEF, DEFINED,
+    // <file.h> or "file.h", only lexed right after #include and #embed
+    HEADER_NAME(String),
+    // The bytes of a file included by #embed, standing for the list
+    // of their values separated by commas. Only made by the preprocessor.
+    // An Arc, since TokenType is used in the static keyword table.
+    EMBED(Arc<[u8]>),
 
-    // Identifiers
-    IDENTIFIER(Buffer),
+    // Identifiers, with universal character names replaced
+    IDENTIFIER(Symbol),
+    // Anything else the source can contain, like a stray `@` or an
+    // unterminated literal (C99 6.4p1). The preprocessor passes it
+    // on, but it is an error once it reaches the parser.
+    OTHER,
     
     ATOM(Atom),
     
@@ -72,10 +147,89 @@ pub enum TokenType {
     EOF
 }
 
-// TODO: String interning
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum Atom {
-    STRING(String),
-    CHAR(String),
+    // The contents of string literals and character constants are
+    // kept as written, see `util::escape`
+    STRING(Encoding, Symbol),
+    CHAR(Encoding, String),
     NUM(String),
-}
\ No newline at end of file
+}
+impl TokenType {
+    /// # Spelling
+    /// Returns the fixed spelling of a keyword or punctuator, or `None`
+    /// for tokens whose spelling depends on the source code, like
+    /// identifiers and atoms. Keywords with more spellings, like `bool`
+    /// and `_Bool`, are spelled the way they were first standardized.
+    pub fn spelling(&self) -> Option<&'static str> {
+        let spelling = match self {
+            TokenType::INT => "int", TokenType::LONG => "long", TokenType::FLOAT => "float",
+            TokenType::DOUBLE => "double", TokenType::VOID => "void", TokenType::CHAR => "char",
+            TokenType::SHORT => "short", TokenType::ENUM => "enum", TokenType::STRUCT => "struct",
+            TokenType::UNION => "union", TokenType::BOOL => "_Bool", TokenType::COMPLEX => "_Complex",
+            TokenType::IMAGINARY => "_Imaginary",
+
+            TokenType::AUTO => "auto", TokenType::EXTERN => "extern",
+            TokenType::REGISTER => "register", TokenType::STATIC => "static",
+
+            TokenType::CONST => "const", TokenType::RESTRICT => "restrict", TokenType::VOLATILE => "volatile",
+            TokenType::ATOMIC => "_Atomic",
+
+            TokenType::UNSIGNED => "unsigned", TokenType::SIGNED => "signed",
+
+            TokenType::INLINE => "inline", TokenType::NORETURN => "_Noreturn",
+
+            TokenType::IF => "if", TokenType::ELSE => "else", TokenType::FOR => "for",
+            TokenType::WHILE => "while", TokenType::BREAK => "break", TokenType::CONTINUE => "continue",
+            TokenType::DO => "do", TokenType::GOTO => "goto", TokenType::SWITCH => "switch",
+            TokenType::CASE => "case", TokenType::DEFAULT => "default", TokenType::RETURN => "return",
+
+            TokenType::TYPEDEF => "typedef", TokenType::SIZEOF => "sizeof", TokenType::ASM => "asm",
+            TokenType::ALIGNAS => "_Alignas", TokenType::ALIGNOF => "_Alignof", TokenType::GENERIC => "_Generic",
+            TokenType::STATIC_ASSERT => "_Static_assert", TokenType::THREAD_LOCAL => "_Thread_local",
+            TokenType::PRAGMA_OPERATOR => "_Pragma",
+
+            TokenType::TRUE => "true", TokenType::FALSE => "false", TokenType::NULLPTR => "nullptr",
+            TokenType::CONSTEXPR => "constexpr", TokenType::TYPEOF => "typeof",
+
+            TokenType::ATTRIBUTE => "__attribute__", TokenType::EXTENSION => "__extension__",
+
+            TokenType::DECLSPEC => "__declspec", TokenType::INT64 => "__int64",
+
+            TokenType::PLUS => "+", TokenType::MINUS => "-", TokenType::ASTERISK => "*",
+            TokenType::SLASH => "/", TokenType::BANG => "!", TokenType::PERCENT => "%",
+            TokenType::SEMICOLON => ";", TokenType::COLON => ":", TokenType::BAR => "|",
+            TokenType::AMPERSAND => "&", TokenType::COMMA => ",", TokenType::DOT => ".",
+            TokenType::ARROW => "->", TokenType::PLUSPLUS => "++", TokenType::MINUSMINUS => "--",
+            TokenType::EQ => "=", TokenType::TRIPLET => "...", TokenType::TILDE => "~",
+            TokenType::DOUBLEAMPERSAND => "&&", TokenType::DOUBLEBAR => "||", TokenType::DOUBLEEQ => "==",
+            TokenType::NEQ => "!=", TokenType::GREATER => ">", TokenType::GREATEREQ => ">=",
+            TokenType::LESS => "<", TokenType::LESSEQ => "<=", TokenType::DOUBLEGREATER => ">>",
+            TokenType::DOUBLELESS => "<<", TokenType::CARET => "^", TokenType::QUESTION => "?",
+            TokenType::HASH => "#", TokenType::HASHTWICE => "##", TokenType::PLUSEQ => "+=",
+            TokenType::MINUSEQ => "-=", TokenType::ASTERISKEQ => "*=", TokenType::SLASHEQ => "/=",
+            TokenType::PERCENTEQ => "%=", TokenType::AMPERSANDEQ => "&=", TokenType::BAREQ => "|=",
+            TokenType::CARETEQ => "^=", TokenType::DOUBLEGREATEREQ => ">>=", TokenType::DOUBLELESSEQ => "<<=",
+            TokenType::LEFT_PAREN => "(", TokenType::RIGHT_PAREN => ")",
+            TokenType::LEFT_BRACKET => "[", TokenType::RIGHT_BRACKET => "]",
+            TokenType::LEFT_BRACE => "{", TokenType::RIGHT_BRACE => "}",
+            TokenType::DOUBLECOLON => "::",
+
+            TokenType::INCLUDE => "include", TokenType::PRAGMA => "pragma", TokenType::IFDEF => "ifdef",
+            TokenType::DEFINE => "define", TokenType::IFNDEF => "ifndef", TokenType::ELIF => "elif",
+            TokenType::ENDIF => "endif", TokenType::LINE => "line", TokenType::ERROR => "error",
+            TokenType::WARNING => "warning", TokenType::UNDEF => "undef", TokenType::DEFINED => "defined",
+
+            TokenType::HEADER_NAME(_) | TokenType::EMBED(_) | TokenType::IDENTIFIER(_)
+            | TokenType::ATOM(_) | TokenType::OTHER | TokenType::EOF => return None,
+        };
+        return Some(spelling);
+    }
+
+    /// # Is Keyword
+    /// Whether the token is a keyword, that is a word with
+    /// a fixed spelling, as opposed to a punctuator.
+    pub fn is_keyword(&self) -> bool {
+        return self.spelling().is_some_and(|spelling| spelling.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'));
+    }
+}
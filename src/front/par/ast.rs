@@ -3,4 +3,3 @@
 //! 
 //! TBD
 
-use super::expr::Expr;
\ No newline at end of file
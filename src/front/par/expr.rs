@@ -12,6 +12,7 @@
 //! - [x] Ternary (? :)
 
 use crate::front::lex::{Token, TokenType};
+use crate::util::escape::Encoding;
 
 #[derive(Clone, Debug)]
 // expr -> binary | unary | atom | group
@@ -45,8 +46,10 @@ pub enum Literal {
     Int(isize),
     UInt(usize),
     Flt(f64),
-    Str(Vec<u8>),
-    Char(u8),
+    /// The bytes of a string literal, as stored on the target
+    Str(Encoding, Vec<u8>),
+    /// The value of a character constant
+    Char(Encoding, i64),
 }
 
 #[derive(Clone, Debug)]
@@ -1,10 +1,15 @@
 //! # Parser
 //! Tornado's C parser.
+
+#![allow(clippy::needless_return)]
+
 // TODO: Add actual grammar definition here
 
 use expr::Literal;
 
-use crate::util::{diag::*, scan::Scanner};
+use crate::util::{diag::*, escape, scan::Scanner};
+use crate::util::diag::err::CompilerError;
+use crate::util::scan::location::Location;
 
 use self::expr::Expr;
 
@@ -19,14 +24,12 @@ mod stmt;
 pub struct Parser<'par> {
     diag: &'par mut Diagnostics,
     scan: Scanner<'par, Token, 3>,
-    ast: Option<Expr>
 }
 
 impl<'par> Parser<'par> {
     pub fn new(tokens: &'par [Token], diag: &'par mut Diagnostics) -> Parser<'par> {
         Self {
-            scan: Scanner::new(tokens, None),
-            ast: None,
+            scan: Scanner::new(tokens),
             diag,
         }
     }
@@ -142,15 +145,26 @@ impl<'par> Parser<'par> {
                 tokentype: TokenType::ATOM(atom),
                 ..
             }) => match atom {
-                Atom::STRING(string) => return Box::new(Some(Expr::Value(Literal::Str(string.as_bytes().to_vec())))),
-                Atom::CHAR(chr)      => return Box::new(Some(Expr::Value(Literal::Char(chr.as_bytes()[0])))),
-                Atom::NUM(num)       => todo!("Number parsing is almost done™"),
+                // The preprocessor already reported malformed escape sequences
+                Atom::STRING(encoding, string) => {
+                    let bytes = escape::to_bytes(&escape::unescape(string, *encoding, &mut vec![]), *encoding);
+                    return Box::new(Some(Expr::Value(Literal::Str(*encoding, bytes))));
+                },
+                Atom::CHAR(encoding, chr) => {
+                    let value = escape::char_value(&escape::unescape(chr, *encoding, &mut vec![]), *encoding);
+                    return Box::new(Some(Expr::Value(Literal::Char(*encoding, value))));
+                },
+                Atom::NUM(_num) => todo!("Number parsing is almost done™"),
             }
             
             
             //return Box::new(Some(Expr::Value(atom.clone()))),
 
-            None => todo!(),
+            None | Some(Token { tokentype: TokenType::EOF, .. }) => {
+                let location = self.scan.lookback[0].map_or_else(Location::new, |tok| tok.location);
+                self.diag.push(Diagnostic::new(CompilerDiagnostic::Error(CompilerError::UNEXPECTED_EOF), location));
+                return Box::new(None);
+            },
             _ => todo!()
         }
     }
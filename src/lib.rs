@@ -1,2 +1,3 @@
+
 pub mod front;
 pub mod util;
\ No newline at end of file
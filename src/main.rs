@@ -42,24 +42,156 @@
  | ██████████████████████████████████████████████████░░░░░░░░░ |
  +-------------------------------------------------------====***/
 
-use tornado_cc::{util::scan::Scanner, *};
+// Explicit returns are used throughout the codebase on purpose
+#![allow(clippy::needless_return)]
+
+use tornado_cc::*;
 
 // TODO: Refactor this whole file.
 fn main() {
    // TODO: Actually use clap
-   //let args = std::env::args().collect::<Vec<String>>();
-   //let source = match args.get(1) {
-   //   Some(arg) => std::fs::read_to_string(arg).unwrap(),
-   //   _ => panic!("A file must be provided.")
-   //};
+   let mut args = std::env::args().skip(1);
+   let mut file = None;
+   let mut paths = front::cpp::SearchPaths::default();
+   let mut stdinc = true;
+   // Like GCC, the default is -std=gnu17
+   let mut standard = util::lang::Standard::C17;
+   let mut extensions = util::lang::Extensions { gnu: true, ..Default::default() };
+   let mut preprocess_only = false;
+   let mut linemarkers = true;
+   let mut trace_macros = false;
+   let mut trigraphs = false;
+   let mut warnings = vec![];
+   // -D, -U, -include and -imacros, in order
+   let mut command_line = vec![];
+   // -M and -MM only write the dependencies, -MD and -MMD also compile
+   let mut depfile = None;
+   let mut dependencies_only = false;
+   let mut depfile_path = None;
+   let mut targets = vec![];
+   let mut phony = false;
+   while let Some(arg) = args.next() {
+      match arg.as_str() {
+         "-I" | "-iquote" | "-isystem" => {
+            let dir = args.next().unwrap_or_else(|| panic!("A directory must be provided after '{arg}'."));
+            match arg.as_str() {
+               "-I" => paths.angled.push(dir.into()),
+               "-iquote" => paths.quote.push(dir.into()),
+               _ => paths.system.push(dir.into()),
+            }
+         },
+         _ if arg.starts_with("-I") => paths.angled.push(arg[2..].into()),
+         _ if arg.starts_with("-iquote") => paths.quote.push(arg[7..].into()),
+         _ if arg.starts_with("-isystem") => paths.system.push(arg[8..].into()),
+         "-D" | "-U" | "-include" | "-imacros" => {
+            let value = args.next().unwrap_or_else(|| panic!("An argument must be provided after '{arg}'."));
+            command_line.push((arg, value));
+         },
+         _ if arg.starts_with("-D") || arg.starts_with("-U") => command_line.push((arg[..2].to_owned(), arg[2..].to_owned())),
+         "-nostdinc" => stdinc = false,
+         "-E" => preprocess_only = true,
+         "-P" => linemarkers = false,
+         "-ftrace-macros" => trace_macros = true,
+         "-trigraphs" => trigraphs = true,
+         "-fms-extensions" => extensions.msvc = true,
+         "-M" | "-MM" | "-MD" | "-MMD" => {
+            // Whether system headers are listed
+            depfile = Some(!arg.starts_with("-MM"));
+            dependencies_only = !arg.ends_with('D');
+         },
+         "-MF" | "-MT" => {
+            let value = args.next().unwrap_or_else(|| panic!("A file must be provided after '{arg}'."));
+            match arg.as_str() {
+               "-MF" => depfile_path = Some(value),
+               _ => targets.push(value),
+            }
+         },
+         _ if arg.starts_with("-MF") => depfile_path = Some(arg[3..].to_owned()),
+         _ if arg.starts_with("-MT") => targets.push(arg[3..].to_owned()),
+         "-MP" => phony = true,
+         _ if arg.starts_with("-Wno-") => warnings.push((arg[5..].to_owned(), false)),
+         _ if arg.starts_with("-W") => warnings.push((arg[2..].to_owned(), true)),
+         _ if arg.starts_with("-std=") => {
+            standard = util::lang::Standard::from_name(&arg[5..])
+               .unwrap_or_else(|| panic!("Unknown standard '{}'.", &arg[5..]));
+            extensions.gnu = arg[5..].starts_with("gnu");
+         },
+         _ => file = Some(arg)
+      }
+   }
+   if stdinc {
+      paths.add_standard();
+   }
 
-   let source = String::from("13 + 2");
+   let file = file.expect("A file must be provided.");
+   let mut sources = util::scan::source::SourceManager::new();
+   let main = match sources.load(file.as_ref()) {
+      Ok(main) => main,
+      Err(err) => {
+         eprintln!("error: {file}: {err}");
+         std::process::exit(1);
+      }
+   };
 
    let mut diagnostics = util::diag::Diagnostics::new();
    
-   let mut lexer = front::lex::Lexer::new(source.as_bytes(), &mut diagnostics);
-   lexer.lex();
-   let tokens = lexer.get_tokens().clone();
+   let mut lexer = front::lex::Lexer::new(sources.source(main), main, &mut diagnostics);
+   lexer.set_trigraphs(trigraphs);
+   lexer.set_standard(standard);
+   lexer.set_extensions(extensions);
+   let tokens = lexer.collect();
+
+   let mut preprocessor = front::cpp::Preprocessor::new(main, tokens, &mut sources, &mut diagnostics);
+   *preprocessor.get_search_paths() = paths;
+   preprocessor.set_standard(standard);
+   preprocessor.set_extensions(extensions);
+   preprocessor.set_trigraphs(trigraphs);
+   for (flag, enabled) in warnings {
+      preprocessor.set_warning(&flag, enabled);
+   }
+   for (option, value) in command_line {
+      match option.as_str() {
+         "-D" => preprocessor.define_macro(&value),
+         "-U" => preprocessor.undefine_macro(&value),
+         "-include" => preprocessor.include_file(&value),
+         _ => preprocessor.include_macros(&value),
+      }
+   }
+   if trace_macros {
+      preprocessor.trace_expansions();
+   }
+   preprocessor.preprocess();
+   if trace_macros {
+      eprint!("{}", preprocessor.dump_expansions());
+   }
+   if !preprocess_only {
+      preprocessor.finish();
+   }
+   let tokens = preprocessor.get_tokens().clone();
+   let text = preprocess_only.then(|| preprocessor.print(linemarkers));
+   if let Some(system) = depfile {
+      // By default the target is the object file, in the current directory
+      let stem = std::path::Path::new(&file).file_stem().unwrap().to_string_lossy().into_owned();
+      if targets.is_empty() {
+         targets.push(format!("{stem}.o"));
+      }
+      let rule = preprocessor.depfile(&targets, system, phony);
+      match depfile_path {
+         Some(path) => std::fs::write(&path, rule).unwrap_or_else(|err| panic!("Cannot write '{path}': {err}.")),
+         None if dependencies_only => print!("{rule}"),
+         None => std::fs::write(format!("{stem}.d"), rule).unwrap_or_else(|err| panic!("Cannot write '{stem}.d': {err}.")),
+      }
+   }
+   for diagnostic in diagnostics.iter() {
+      eprintln!("{}", diagnostic.display(&sources));
+   }
+   if dependencies_only && depfile.is_some() {
+      return;
+   }
+   if let Some(text) = text {
+      print!("{text}");
+      return;
+   }
    println!("{:#?}", tokens);
 
    let mut parser = front::par::Parser::new(&tokens, &mut diagnostics);
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone)]
 pub enum CompilerError {
@@ -52,4 +54,432 @@ pub enum CompilerError {
     /// }
     /// ``` 
     UNKNOWN_PREPROCESSOR_DIRECTIVE,
+
+    /// # MISSING_MACRO_NAME
+    /// This error is triggered when a `#define` or
+    /// `#undef` directive is not followed by the
+    /// name of the macro.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define // Missing macro name
+    /// ```
+    MISSING_MACRO_NAME,
+
+    /// # INVALID_MACRO_NAME
+    /// This error is triggered when the name of a
+    /// macro is not an identifier, or when trying to
+    /// define a macro called `defined`.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define 42 answer // Macro names must be identifiers
+    /// #define defined   // `defined` cannot be used as a macro name
+    /// ```
+    INVALID_MACRO_NAME,
+
+    /// # MALFORMED_MACRO_PARAMETERS
+    /// This error is triggered when the parameter
+    /// list of a function-like macro is not a comma
+    /// separated list of unique identifiers closed
+    /// by a `)`.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define MAX(a b) ((a) > (b) ? (a) : (b)) // Missing ','
+    /// #define MIN(a, a) ((a) < (a) ? (a) : (a)) // Duplicate parameter
+    /// ```
+    MALFORMED_MACRO_PARAMETERS,
+
+    /// # UNTERMINATED_MACRO_INVOCATION
+    /// This error is triggered when the end of the
+    /// file is reached while collecting the arguments
+    /// of a function-like macro.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define ID(x) x
+    /// int a = ID(42; // Missing ')'
+    /// // end of file here
+    /// ```
+    UNTERMINATED_MACRO_INVOCATION,
+
+    /// # MACRO_ARGUMENT_MISMATCH
+    /// This error is triggered when a function-like
+    /// macro is invoked with a different number of
+    /// arguments than the number of parameters it
+    /// was defined with.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define ADD(a, b) ((a) + (b))
+    /// int a = ADD(1, 2, 3); // Too many arguments
+    /// int b = ADD(1);       // Too few arguments
+    /// ```
+    MACRO_ARGUMENT_MISMATCH,
+
+    /// # MALFORMED_INCLUDE
+    /// This error is triggered when `#include` is not
+    /// followed by a header name, either `"FILENAME"`
+    /// or `<FILENAME>`, even after macro expansion.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #include stdio.h // Missing '<' and '>'
+    /// ```
+    MALFORMED_INCLUDE,
+
+    /// # INCLUDE_NOT_FOUND
+    /// This error is triggered when the file named
+    /// by an `#include` directive cannot be found in
+    /// any of the include directories.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #include "does_not_exist.h" // File not found
+    /// ```
+    INCLUDE_NOT_FOUND(String),
+
+    /// # INCLUDE_TOO_DEEP
+    /// This error is triggered when files include
+    /// each other more than 200 levels deep, which
+    /// usually means a header without an include
+    /// guard includes itself.
+    /// 
+    /// ## Example
+    /// ```c
+    /// // self.h
+    /// #include "self.h" // Includes itself forever
+    /// ```
+    INCLUDE_TOO_DEEP,
+
+    /// # MALFORMED_CONDITION
+    /// This error is triggered when the expression
+    /// of an `#if` or `#elif` directive is missing or
+    /// is not a valid integer constant expression.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #if          // Missing expression
+    /// #endif
+    /// #if (1 + 2   // Missing ')'
+    /// #endif
+    /// #if 1.5      // Floating point numbers are not allowed
+    /// #endif
+    /// ```
+    MALFORMED_CONDITION,
+
+    /// # DIVISION_BY_ZERO
+    /// This error is triggered when the expression
+    /// of an `#if` or `#elif` directive divides by
+    /// zero. Divisions that are never evaluated, like
+    /// the right hand side of `0 && 1 / 0`, are fine.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #if 1 / 0 // Division by zero
+    /// #endif
+    /// ```
+    DIVISION_BY_ZERO,
+
+    /// # UNTERMINATED_CONDITIONAL
+    /// This error is triggered when the end of a file
+    /// is reached before the `#endif` of an `#if`,
+    /// `#ifdef` or `#ifndef` directive. Every file must
+    /// close the conditionals it opens.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #ifdef DEBUG // Unterminated conditional
+    /// int debug = 1;
+    /// // end of file here (Missing '#endif')
+    /// ```
+    UNTERMINATED_CONDITIONAL,
+
+    /// # UNMATCHED_CONDITIONAL
+    /// This error is triggered when an `#elif`, `#else`
+    /// or `#endif` directive does not belong to any
+    /// `#if`, `#ifdef` or `#ifndef` directive of the
+    /// same file.
+    /// 
+    /// ## Example
+    /// ```c
+    /// int a;
+    /// #endif // No matching '#if'
+    /// ```
+    UNMATCHED_CONDITIONAL,
+
+    /// # ELSE_AFTER_ELSE
+    /// This error is triggered when an `#elif` or
+    /// `#else` directive follows the `#else` of the
+    /// same conditional.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #ifdef DEBUG
+    /// #else
+    /// #else  // '#else' after '#else'
+    /// #endif
+    /// ```
+    ELSE_AFTER_ELSE,
+
+    /// # MISPLACED_STRINGIFY
+    /// This error is triggered when the `#` operator
+    /// in the body of a function-like macro is not
+    /// followed by one of the macro parameters.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define STR(x) #y // 'y' is not a parameter
+    /// ```
+    MISPLACED_STRINGIFY,
+
+    /// # MISPLACED_PASTE
+    /// This error is triggered when the `##` operator
+    /// is the first or the last token of the body of
+    /// a macro, and so it is missing one of its
+    /// operands.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define CAT(a, b) a b ## // Missing right operand
+    /// ```
+    MISPLACED_PASTE,
+
+    /// # INVALID_PASTE
+    /// This error is triggered when pasting two tokens
+    /// together with `##` does not produce a single
+    /// valid token. The two tokens are left as they
+    /// are.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define CAT(a, b) a ## b
+    /// CAT(+, -) // '+-' is not a valid token
+    /// ```
+    INVALID_PASTE,
+
+    /// # MALFORMED_VA_OPT
+    /// This error is triggered when `__VA_OPT__` in the
+    /// body of a variadic macro is not followed by a
+    /// parenthesized list of tokens, or when it is
+    /// nested in another `__VA_OPT__`.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define F(...) f(0 __VA_OPT__(, __VA_ARGS__) // Missing ')'
+    /// ```
+    MALFORMED_VA_OPT,
+
+    /// # ERROR_DIRECTIVE
+    /// This error is triggered by the `#error`
+    /// directive, and carries the message that
+    /// follows it.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #ifndef __linux__
+    /// #error Only Linux is supported
+    /// #endif
+    /// ```
+    ERROR_DIRECTIVE(String),
+
+    /// # PRAGMA_ERROR
+    /// This error is triggered by `#pragma GCC error`,
+    /// and carries the string literal that follows it.
+    ///
+    /// ## Example
+    /// ```c
+    /// #pragma GCC error "this header is not for C89"
+    /// ```
+    PRAGMA_ERROR(String),
+
+    /// # MALFORMED_LINE_DIRECTIVE
+    /// This error is triggered when `#line` is not
+    /// followed by a line number in the range
+    /// 1..=2147483647 and, optionally, a file name.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #line 0x10 "file.c" // Not a decimal number
+    /// ```
+    MALFORMED_LINE_DIRECTIVE,
+
+    /// # MALFORMED_PRAGMA_OPERATOR
+    /// This error is triggered when `_Pragma` is
+    /// not followed by a string literal between
+    /// parentheses.
+    /// 
+    /// ## Example
+    /// ```c
+    /// _Pragma(once) // Missing quotes
+    /// ```
+    MALFORMED_PRAGMA_OPERATOR,
+
+    /// # MALFORMED_EMBED
+    /// This error is triggered when `#embed` is not
+    /// followed by a header name, or when one of its
+    /// parameters is malformed or given twice.
+    ///
+    /// ## Example
+    /// ```c
+    /// #embed "font.bin" limit(-1) // Negative limit
+    /// ```
+    MALFORMED_EMBED,
+
+    /// # UNKNOWN_EMBED_PARAMETER
+    /// This error is triggered when `#embed` is given
+    /// a parameter it does not know about.
+    ///
+    /// ## Example
+    /// ```c
+    /// #embed "font.bin" offset(16) // Not a standard parameter
+    /// ```
+    UNKNOWN_EMBED_PARAMETER(String),
+
+    /// # UNTERMINATED_LITERAL
+    /// This error is triggered when a string literal
+    /// or character constant is not closed on the line
+    /// it starts on. It carries the missing quote.
+    ///
+    /// ## Example
+    /// ```c
+    /// char *s = "hello; // Missing '"'
+    /// ```
+    UNTERMINATED_LITERAL(char),
+
+    /// # EMPTY_CHAR_CONSTANT
+    /// This error is triggered by a character constant
+    /// with no characters in it.
+    ///
+    /// ## Example
+    /// ```c
+    /// char c = ''; // Empty
+    /// ```
+    EMPTY_CHAR_CONSTANT,
+
+    /// # MISSING_HEX_DIGITS
+    /// This error is triggered when a `\x` escape
+    /// sequence is not followed by any hexadecimal
+    /// digit.
+    ///
+    /// ## Example
+    /// ```c
+    /// char *s = "\xg";
+    /// ```
+    MISSING_HEX_DIGITS,
+
+    /// # ESCAPE_OUT_OF_RANGE
+    /// This error is triggered when the value of an
+    /// octal or hexadecimal escape sequence does not
+    /// fit in a `char`.
+    ///
+    /// ## Example
+    /// ```c
+    /// char *s = "\x100"; // 256
+    /// ```
+    ESCAPE_OUT_OF_RANGE,
+
+    /// # INVALID_UNIVERSAL_CHARACTER
+    /// This error is triggered when a `\u` or `\U`
+    /// escape sequence has too few hexadecimal digits,
+    /// or does not name a character it can name.
+    ///
+    /// ## Example
+    /// ```c
+    /// char *s = "\u0041"; // 'A' can't be named like this
+    /// ```
+    INVALID_UNIVERSAL_CHARACTER,
+
+    /// # INCOMPATIBLE_STRING_CONCATENATION
+    /// This error is triggered when adjacent string
+    /// literals have different encoding prefixes.
+    ///
+    /// ## Example
+    /// ```c
+    /// wchar_t *s = L"wide" u8"utf-8";
+    /// ```
+    INCOMPATIBLE_STRING_CONCATENATION,
+
+    /// # INVALID_UTF8
+    /// This error is triggered when the source code
+    /// is not valid UTF-8. The invalid bytes are
+    /// skipped.
+    INVALID_UTF8,
+
+    /// # STRAY_CHARACTER
+    /// This error is triggered by a character that
+    /// can't start any token, unless it is only seen
+    /// by the preprocessor, like in `#if 0`.
+    ///
+    /// ## Example
+    /// ```c
+    /// int x = 1 → 2; // Not an operator
+    /// ```
+    STRAY_CHARACTER(char),
+
+    /// # UNTERMINATED_COMMENT
+    /// This error is triggered by a `/*` comment
+    /// that is never closed by a `*/`.
+    ///
+    /// ## Example
+    /// ```c
+    /// int x; /* The rest of the file is a comment
+    /// ```
+    UNTERMINATED_COMMENT,
+
+    /// # INVALID_INTEGER_SUFFIX
+    /// This error is triggered when an integer constant
+    /// ends with something other than `u`, `l`, `ll` or
+    /// `wb`, in any case and order.
+    ///
+    /// ## Example
+    /// ```c
+    /// #if 1uu // Only one u
+    /// #endif
+    /// ```
+    INVALID_INTEGER_SUFFIX(String),
+}
+
+impl fmt::Display for CompilerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MALFORMED_NUMBER => write!(f, "malformed number"),
+            Self::UNEXPECTED_EOF => write!(f, "unexpected end of file"),
+            Self::UNKNOWN_PREPROCESSOR_DIRECTIVE => write!(f, "unknown preprocessor directive"),
+            Self::MISSING_MACRO_NAME => write!(f, "no macro name given"),
+            Self::INVALID_MACRO_NAME => write!(f, "macro names must be identifiers"),
+            Self::MALFORMED_MACRO_PARAMETERS => write!(f, "malformed macro parameter list"),
+            Self::UNTERMINATED_MACRO_INVOCATION => write!(f, "unterminated argument list invoking macro"),
+            Self::MACRO_ARGUMENT_MISMATCH => write!(f, "wrong number of arguments passed to macro"),
+            Self::MALFORMED_INCLUDE => write!(f, "#include expects \"FILENAME\" or <FILENAME>"),
+            Self::INCLUDE_NOT_FOUND(name) => write!(f, "'{name}' file not found"),
+            Self::INCLUDE_TOO_DEEP => write!(f, "#include nested too deeply"),
+            Self::MALFORMED_CONDITION => write!(f, "invalid preprocessor expression"),
+            Self::DIVISION_BY_ZERO => write!(f, "division by zero in preprocessor expression"),
+            Self::UNTERMINATED_CONDITIONAL => write!(f, "unterminated conditional directive"),
+            Self::UNMATCHED_CONDITIONAL => write!(f, "conditional directive without #if"),
+            Self::ELSE_AFTER_ELSE => write!(f, "#else or #elif after #else"),
+            Self::MISPLACED_STRINGIFY => write!(f, "'#' is not followed by a macro parameter"),
+            Self::MISPLACED_PASTE => write!(f, "'##' cannot appear at either end of a macro expansion"),
+            Self::INVALID_PASTE => write!(f, "pasting does not give a valid preprocessing token"),
+            Self::MALFORMED_VA_OPT => write!(f, "__VA_OPT__ must be followed by a parenthesized list of tokens"),
+            Self::ERROR_DIRECTIVE(message) => write!(f, "#error {message}"),
+            Self::PRAGMA_ERROR(message) => write!(f, "{message}"),
+            Self::MALFORMED_LINE_DIRECTIVE => write!(f, "#line must be followed by a line number and an optional file name"),
+            Self::MALFORMED_PRAGMA_OPERATOR => write!(f, "_Pragma takes a parenthesized string literal"),
+            Self::MALFORMED_EMBED => write!(f, "#embed expects \"FILENAME\" or <FILENAME> followed by parameters"),
+            Self::UNKNOWN_EMBED_PARAMETER(name) => write!(f, "unknown #embed parameter '{name}'"),
+            Self::UNTERMINATED_LITERAL(quote) => write!(f, "missing terminating {quote} character"),
+            Self::EMPTY_CHAR_CONSTANT => write!(f, "empty character constant"),
+            Self::MISSING_HEX_DIGITS => write!(f, "\\x used with no following hex digits"),
+            Self::ESCAPE_OUT_OF_RANGE => write!(f, "escape sequence out of range"),
+            Self::INVALID_UNIVERSAL_CHARACTER => write!(f, "incomplete or invalid universal character name"),
+            Self::INCOMPATIBLE_STRING_CONCATENATION => write!(f, "concatenation of string literals with different encoding prefixes"),
+            Self::INVALID_UTF8 => write!(f, "invalid UTF-8 in source file"),
+            Self::STRAY_CHARACTER(c) => write!(f, "stray '{c}' in program"),
+            Self::UNTERMINATED_COMMENT => write!(f, "unterminated comment"),
+            Self::INVALID_INTEGER_SUFFIX(suffix) => write!(f, "invalid suffix '{suffix}' on integer constant"),
+        }
+    }
 }
\ No newline at end of file
@@ -1,10 +1,18 @@
+#![allow(clippy::needless_return)]
+
 pub mod warn;
 pub mod err;
 
+use std::fmt;
+use std::path::PathBuf;
+use std::rc::Rc;
+
 use warn::CompilerWarning;
 use err::CompilerError;
 
+use super::intern::Symbol;
 use super::scan::location::Location;
+use super::scan::source::SourceManager;
 
 #[derive(Debug, Clone)]
 /// # CompilerDiagnostic
@@ -16,23 +24,76 @@ pub enum CompilerDiagnostic {
     // Note(CompilerNote) //??
 }
 
+impl fmt::Display for CompilerDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning(warning) => write!(f, "warning: {warning}"),
+            Self::Error(error) => write!(f, "error: {error}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd)]
+/// # Origin
+/// The file a `Diagnostic` belongs to, together with the
+/// `#include` directive that file was included from, if
+/// any. Following `included_from` gives the whole include
+/// stack, innermost file first.
+pub struct Origin {
+    pub file: PathBuf,
+    pub included_from: Option<(Rc<Origin>, Location)>,
+    /// ## Delta
+    /// How much `#line` moved the line numbers of the file,
+    /// see `line()`.
+    pub delta: isize,
+}
+
+impl Origin {
+    /// # Line
+    /// The line `location` appears to be at after `#line`,
+    /// counted from 0.
+    pub fn line(&self, sources: &SourceManager, location: Location) -> usize {
+        return sources.line(location).saturating_add_signed(self.delta);
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd)]
+/// # Expansion
+/// The macro expansion a token comes from. Following `parent`
+/// gives the whole chain of expansions, innermost macro first:
+/// in `#define A B` and `#define B x`, the `x` coming from `A`
+/// has the expansion of `B`, whose parent is the one of `A`.
+pub struct Expansion {
+    pub name: Symbol,
+    /// ## Location
+    /// Where the macro is defined.
+    pub location: Location,
+    /// ## Origin
+    /// The file the macro is defined in, none for the builtin
+    /// macros.
+    pub origin: Option<Rc<Origin>>,
+    pub parent: Option<Rc<Expansion>>,
+}
+
 #[derive(Debug)]
 /// # Diagnostic
 /// A Struct containing a `CompilerDiagnostic` and a location.
 /// It represents any error or warning generated by the compiler
 /// at a location in code.
 /// 
-/// For example: an error of type `UNEXPECTED_EOF` on line 4,
-/// column 12 can be represented like this:
+/// For example: an error of type `UNEXPECTED_EOF` at the
+/// byte 12 of the main file can be represented like this:
 /// ```ignore
 /// Diagnostic {
 ///     diagnostic: CompilerDiagnostic::Error(
 ///         CompilerError::UNEXPECTED_EOF
 ///     ),
 ///     location: Location {
-///         position: 12,
-///         line: 4
-///     }
+///         file: main,
+///         offset: 12
+///     },
+///     origin: None,
+///     expansion: None
 /// }
 /// ```
 /// 
@@ -40,11 +101,77 @@ pub enum CompilerDiagnostic {
 pub struct Diagnostic {
     pub diagnostic: CompilerDiagnostic,
     pub location: Location,
+    pub origin: Option<Rc<Origin>>,
+    /// ## Expansion
+    /// The macro expansion the diagnostic happened in, if any.
+    pub expansion: Option<Rc<Expansion>>,
 }
 
 impl Diagnostic {
     pub fn new(diag: CompilerDiagnostic, location: Location) -> Diagnostic {
-        return Diagnostic { diagnostic: diag, location};
+        return Diagnostic { diagnostic: diag, location, origin: None, expansion: None };
+    }
+}
+
+impl Diagnostic {
+    /// # Display
+    /// Prints the diagnostic the way GCC does, preceded by
+    /// the chain of files it was included from, and followed
+    /// by the chain of macros it was expanded from, like clang:
+    /// ```text
+    /// In file included from b.h:1,
+    ///                  from main.c:2:
+    /// a.h:5:9: error: pasting does not give a valid token
+    /// a.h:2:23: note: expanded from macro 'PASTE'
+    /// ```
+    /// Lines and columns are looked up in `sources`.
+    pub fn display<'a>(&'a self, sources: &'a SourceManager) -> impl fmt::Display + 'a {
+        return DisplayDiagnostic { diagnostic: self, sources };
+    }
+}
+
+struct DisplayDiagnostic<'a> {
+    diagnostic: &'a Diagnostic,
+    sources: &'a SourceManager,
+}
+
+impl fmt::Display for DisplayDiagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (diag, sources) = (self.diagnostic, self.sources);
+        // Lines and columns are counted from 0
+        let column = sources.column(diag.location) + 1;
+        match diag.origin {
+            Some(ref origin) => {
+                let mut parent = &origin.included_from;
+                let mut first = true;
+                while let Some((ref file, location)) = parent {
+                    let prefix = if first { "In file included from" } else { "                 from" };
+                    let suffix = if file.included_from.is_some() { "," } else { ":" };
+                    writeln!(f, "{prefix} {}:{}{suffix}", file.file.display(), file.line(sources, *location) + 1)?;
+                    parent = &file.included_from;
+                    first = false;
+                }
+                let line = origin.line(sources, diag.location) + 1;
+                write!(f, "{}:{line}:{column}: {}", origin.file.display(), diag.diagnostic)?;
+            },
+            None => {
+                let line = sources.line(diag.location) + 1;
+                write!(f, "{}:{line}:{column}: {}", sources.path(diag.location.file).display(), diag.diagnostic)?
+            }
+        }
+        let mut expansion = &diag.expansion;
+        while let Some(ref mac) = expansion {
+            match mac.origin {
+                Some(ref origin) => {
+                    let (line, column) = (origin.line(sources, mac.location) + 1, sources.column(mac.location) + 1);
+                    write!(f, "\n{}:{line}:{column}: ", origin.file.display())?
+                },
+                None => write!(f, "\n<built-in>: ")?
+            }
+            write!(f, "note: expanded from macro '{}'", mac.name)?;
+            expansion = &mac.parent;
+        }
+        return Ok(());
     }
 }
 
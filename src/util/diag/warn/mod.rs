@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone)]
 pub enum CompilerWarning {
@@ -6,7 +8,8 @@ pub enum CompilerWarning {
     /// This warning is emitted when a builtin
     /// macro is undefined, either via the
     /// `#undef` preprocessor directive or via
-    /// the `-U` commandline attribute.
+    /// the `-U` commandline attribute, or when
+    /// it is redefined via `#define`.
     /// 
     /// ## Examples
     /// 
@@ -39,4 +42,139 @@ pub enum CompilerWarning {
     /// }
     /// ``` 
     MISSING_PREPROCESSOR_DIRECTIVE,
+
+    // gcc and clang -Wmacro-redefined
+    /// # MACRO_REDEFINED
+    /// This warning is emitted when a macro is
+    /// defined again with a different replacement
+    /// list or different parameters, without being
+    /// undefined first.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #define SIZE 16
+    /// #define SIZE 32 // Macro redefined here
+    /// ```
+    MACRO_REDEFINED,
+
+    /// # WARNING_DIRECTIVE
+    /// This warning is emitted by the `#warning`
+    /// directive, and carries the message that
+    /// follows it.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #warning This header is deprecated
+    /// ```
+    WARNING_DIRECTIVE(String),
+
+    /// # PRAGMA_WARNING
+    /// This warning is emitted by `#pragma GCC warning`,
+    /// and carries the string literal that follows it.
+    ///
+    /// ## Example
+    /// ```c
+    /// #pragma GCC warning "this header is deprecated"
+    /// ```
+    PRAGMA_WARNING(String),
+
+    // gcc and clang -Wunknown-pragmas
+    /// # UNKNOWN_PRAGMA
+    /// This warning is emitted when no handler
+    /// knows the pragma. The pragma is ignored.
+    /// It is disabled by default, and enabled
+    /// by `-Wunknown-pragmas`.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #pragma unroll_all_the_loops
+    /// ```
+    UNKNOWN_PRAGMA,
+
+    // gcc -Wpragmas
+    /// # MALFORMED_PRAGMA
+    /// This warning is emitted when a known pragma
+    /// is used with the wrong syntax. The pragma
+    /// is ignored.
+    /// 
+    /// ## Example
+    /// ```c
+    /// #pragma pack 4 // Missing parentheses
+    /// ```
+    MALFORMED_PRAGMA,
+
+    // clang -Wunknown-escape-sequence
+    /// # UNKNOWN_ESCAPE_SEQUENCE
+    /// This warning is emitted when a backslash is
+    /// followed by a character that does not make an
+    /// escape sequence. The backslash is ignored.
+    ///
+    /// ## Example
+    /// ```c
+    /// char *s = "\q";
+    /// ```
+    UNKNOWN_ESCAPE_SEQUENCE(char),
+
+    // gcc -Wmultichar
+    /// # MULTICHAR_CONSTANT
+    /// This warning is emitted by character constants
+    /// made of more than one character, whose value
+    /// is implementation defined.
+    ///
+    /// ## Example
+    /// ```c
+    /// int magic = 'ELF';
+    /// ```
+    MULTICHAR_CONSTANT,
+
+    // gcc -Wtrigraphs
+    /// # TRIGRAPH_IGNORED
+    /// This warning is emitted by trigraphs when
+    /// they are not enabled with `-trigraphs`, as
+    /// they are left as they are.
+    ///
+    /// ## Example
+    /// ```c
+    /// puts("what??!"); // "what|" with -trigraphs
+    /// ```
+    TRIGRAPH_IGNORED(char),
+}
+
+impl CompilerWarning {
+    /// # Flag
+    /// Returns the name of the `-W` flag controlling the
+    /// warning, as used by `-Wno-...` and by
+    /// `#pragma GCC diagnostic`. Warnings with no flag
+    /// can't be disabled.
+    pub fn flag(&self) -> Option<&'static str> {
+        match self {
+            Self::UNDEFINED_BUILTIN_MACRO => return Some("builtin-macro-redefined"),
+            Self::MISSING_PREPROCESSOR_DIRECTIVE => return None,
+            Self::MACRO_REDEFINED => return Some("macro-redefined"),
+            Self::WARNING_DIRECTIVE(_) => return Some("cpp"),
+            Self::PRAGMA_WARNING(_) => return None,
+            Self::UNKNOWN_PRAGMA => return Some("unknown-pragmas"),
+            Self::MALFORMED_PRAGMA => return Some("pragmas"),
+            Self::UNKNOWN_ESCAPE_SEQUENCE(_) => return Some("unknown-escape-sequence"),
+            Self::MULTICHAR_CONSTANT => return Some("multichar"),
+            Self::TRIGRAPH_IGNORED(_) => return Some("trigraphs"),
+        }
+    }
+}
+
+impl fmt::Display for CompilerWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UNDEFINED_BUILTIN_MACRO => write!(f, "undefining or redefining builtin macro"),
+            Self::MISSING_PREPROCESSOR_DIRECTIVE => write!(f, "missing preprocessor directive"),
+            Self::MACRO_REDEFINED => write!(f, "macro redefined"),
+            Self::WARNING_DIRECTIVE(message) => write!(f, "#warning {message}"),
+            Self::PRAGMA_WARNING(message) => write!(f, "{message}"),
+            Self::UNKNOWN_PRAGMA => write!(f, "unknown pragma ignored"),
+            Self::MALFORMED_PRAGMA => write!(f, "malformed pragma ignored"),
+            Self::UNKNOWN_ESCAPE_SEQUENCE(c) => write!(f, "unknown escape sequence '\\{c}'"),
+            Self::MULTICHAR_CONSTANT => write!(f, "multi-character character constant"),
+            Self::TRIGRAPH_IGNORED(c) => write!(f, "trigraph ??{c} ignored, use -trigraphs to enable"),
+        }
+    }
 }
\ No newline at end of file
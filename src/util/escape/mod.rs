@@ -0,0 +1,269 @@
+//! # Escape
+//! Escape sequences in string literals and character constants
+//! (C99 6.4.4.4), and the encoding of their contents.
+//!
+//! The lexer keeps literals as they are written, escapes
+//! included, since the preprocessor needs their spelling. They
+//! are only turned into the code units they stand for by
+//! `unescape()` once they are used (translation phase 5), which
+//! is when their escape sequences are checked.
+//!
+//! The execution character set is UTF-8 for `char`, and UTF-16
+//! or UTF-32 for the wider types, depending on the prefix of the
+//! literal (see `Encoding`).
+//!
+//! ## Supported escape sequences
+//! - `\'`, `\"`, `\?`, `\\`, `\a`, `\b`, `\f`, `\n`, `\r`, `\t`, `\v`
+//! - `\e`, the escape character (GNU)
+//! - `\0` to `\777`, one to three octal digits
+//! - `\x` followed by any number of hexadecimal digits
+//! - `\uXXXX` and `\UXXXXXXXX`, universal character names
+
+#![allow(clippy::needless_return)]
+
+use super::diag::CompilerDiagnostic;
+use super::diag::err::CompilerError;
+use super::diag::warn::CompilerWarning;
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+/// # Encoding
+/// The encoding of a literal, given by its prefix, which also
+/// tells the type of its elements.
+pub enum Encoding {
+    /// No prefix, `char`
+    Char,
+    /// `u8`, `char8_t`
+    Utf8,
+    /// `u`, `char16_t`
+    Utf16,
+    /// `U`, `char32_t`
+    Utf32,
+    /// `L`, `wchar_t`
+    Wide,
+}
+
+impl Encoding {
+    pub fn from_prefix(prefix: &[u8]) -> Option<Encoding> {
+        match prefix {
+            b"" => return Some(Encoding::Char),
+            b"u8" => return Some(Encoding::Utf8),
+            b"u" => return Some(Encoding::Utf16),
+            b"U" => return Some(Encoding::Utf32),
+            b"L" => return Some(Encoding::Wide),
+            _ => return None
+        }
+    }
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Encoding::Char => return "",
+            Encoding::Utf8 => return "u8",
+            Encoding::Utf16 => return "u",
+            Encoding::Utf32 => return "U",
+            Encoding::Wide => return "L",
+        }
+    }
+
+    /// # Width
+    /// The size in bytes of a code unit. `wchar_t` is 16 bits wide
+    /// on Windows, and 32 everywhere else.
+    pub fn width(self) -> usize {
+        match self {
+            Encoding::Char | Encoding::Utf8 => return 1,
+            Encoding::Utf16 => return 2,
+            Encoding::Utf32 => return 4,
+            Encoding::Wide if cfg!(windows) => return 2,
+            Encoding::Wide => return 4,
+        }
+    }
+
+    /// The largest value of a code unit.
+    fn max(self) -> u32 {
+        return (u64::MAX >> (64 - 8 * self.width())) as u32;
+    }
+
+    /// # Encode
+    /// Appends the code units of `c` to `units`.
+    fn encode(self, c: char, units: &mut Vec<u32>) {
+        match self.width() {
+            1 => units.extend(c.encode_utf8(&mut [0; 4]).bytes().map(u32::from)),
+            2 => units.extend(c.encode_utf16(&mut [0; 2]).iter().map(|unit| u32::from(*unit))),
+            _ => units.push(c as u32)
+        }
+    }
+}
+
+/// # Universal Character
+/// Whether `code` can be named by `\u` or `\U`: any character
+/// but the surrogates and the basic character set, apart from
+/// `$`, `@` and `` ` `` (C99 6.4.3p2).
+pub fn universal_character(code: u32) -> Option<char> {
+    if code < 0xA0 && !matches!(code, 0x24 | 0x40 | 0x60) {
+        return None;
+    }
+    return char::from_u32(code);
+}
+
+/// # Unescape
+/// Returns the code units `raw`, the contents of a literal without
+/// its quotes, stands for in `encoding`. Malformed escape sequences
+/// are pushed to `diag`, and replaced by something sensible.
+pub fn unescape(raw: &str, encoding: Encoding, diag: &mut Vec<CompilerDiagnostic>) -> Vec<u32> {
+    let mut units = Vec::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            encoding.encode(c, &mut units);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            // The lexer never ends a literal with a lone backslash
+            units.push(u32::from(b'\\'));
+            break;
+        };
+        match escape {
+            '\'' | '"' | '?' | '\\' => units.push(escape as u32),
+            'a' => units.push(0x07),
+            'b' => units.push(0x08),
+            'f' => units.push(0x0C),
+            'n' => units.push(0x0A),
+            'r' => units.push(0x0D),
+            't' => units.push(0x09),
+            'v' => units.push(0x0B),
+            'e' | 'E' => units.push(0x1B),
+            '0' ..= '7' => {
+                let mut value = escape.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => value = value * 8 + digit,
+                        None => break
+                    }
+                    chars.next();
+                }
+                if value > encoding.max() {
+                    diag.push(CompilerDiagnostic::Error(CompilerError::ESCAPE_OUT_OF_RANGE));
+                }
+                units.push(value & encoding.max());
+            },
+            'x' => {
+                let mut value: u64 = 0;
+                let mut digits = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    value = value.saturating_mul(16).saturating_add(u64::from(digit));
+                    digits += 1;
+                    chars.next();
+                }
+                if digits == 0 {
+                    diag.push(CompilerDiagnostic::Error(CompilerError::MISSING_HEX_DIGITS));
+                } else if value > u64::from(encoding.max()) {
+                    diag.push(CompilerDiagnostic::Error(CompilerError::ESCAPE_OUT_OF_RANGE));
+                }
+                units.push(value as u32 & encoding.max());
+            },
+            'u' | 'U' => {
+                let len = if escape == 'u' { 4 } else { 8 };
+                let mut value: u32 = 0;
+                let mut digits = 0;
+                while digits < len {
+                    let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) else { break; };
+                    value = value * 16 + digit;
+                    digits += 1;
+                    chars.next();
+                }
+                match universal_character(value) {
+                    Some(c) if digits == len => encoding.encode(c, &mut units),
+                    _ => diag.push(CompilerDiagnostic::Error(CompilerError::INVALID_UNIVERSAL_CHARACTER))
+                }
+            },
+            other => {
+                // Like GCC, the backslash is dropped
+                diag.push(CompilerDiagnostic::Warning(CompilerWarning::UNKNOWN_ESCAPE_SEQUENCE(other)));
+                encoding.encode(other, &mut units);
+            }
+        }
+    }
+    return units;
+}
+
+/// # Unescape Identifier
+/// Replaces the universal character names of an identifier
+/// with the characters they name, so that `caf\u00E9` and
+/// `café` are the same identifier. The lexer made sure they
+/// are valid.
+pub fn unescape_identifier(raw: &str) -> String {
+    if !raw.contains('\\') {
+        return raw.to_owned();
+    }
+    let mut identifier = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(i) = rest.find('\\') {
+        identifier.push_str(&rest[..i]);
+        let len = if rest[i + 1..].starts_with('u') { 4 } else { 8 };
+        let code = u32::from_str_radix(&rest[i + 2..i + 2 + len], 16).unwrap();
+        identifier.push(char::from_u32(code).unwrap());
+        rest = &rest[i + 2 + len..];
+    }
+    identifier.push_str(rest);
+    return identifier;
+}
+
+/// # Escape
+/// The opposite of `unescape()`: returns the contents of a literal
+/// standing for `units`. Anything that is not printable ASCII is
+/// written as a hexadecimal escape, and so are the hexadecimal
+/// digits following one, which it would swallow otherwise.
+pub fn escape(units: &[u32]) -> String {
+    let mut raw = String::with_capacity(units.len());
+    let mut hex = false;
+    for unit in units.iter().copied() {
+        let c = char::from_u32(unit).filter(|c| c.is_ascii_graphic() || *c == ' ');
+        match c {
+            Some(c) if !(hex && c.is_ascii_hexdigit()) => {
+                if c == '"' || c == '\\' {
+                    raw.push('\\');
+                }
+                raw.push(c);
+                hex = false;
+            },
+            _ => {
+                raw.push_str(&format!("\\x{unit:x}"));
+                hex = true;
+            }
+        }
+    }
+    return raw;
+}
+
+/// # To Bytes
+/// Lays out `units` in memory, as they are stored on the target.
+pub fn to_bytes(units: &[u32], encoding: Encoding) -> Vec<u8> {
+    let width = encoding.width();
+    let mut bytes = Vec::with_capacity(units.len() * width);
+    for unit in units {
+        match width {
+            1 => bytes.push(*unit as u8),
+            2 => bytes.extend((*unit as u16).to_ne_bytes()),
+            _ => bytes.extend(unit.to_ne_bytes())
+        }
+    }
+    return bytes;
+}
+
+/// # Char Value
+/// The value of a character constant made of `units`, as GCC
+/// computes it. A single `char` is sign extended, while the `int`
+/// value of a multicharacter constant like `'ab'` is made of its
+/// characters, the last one in the lowest byte. The wider types
+/// only keep their last character: `char16_t` and `char32_t` are
+/// unsigned, `wchar_t` is signed.
+pub fn char_value(units: &[u32], encoding: Encoding) -> i64 {
+    let Some(last) = units.last() else { return 0; };
+    match (encoding, units) {
+        (Encoding::Char, [byte]) => return *byte as u8 as i8 as i64,
+        (Encoding::Char, _) => return units.iter().fold(0i64, |value, byte| (value << 8 | *byte as i64) as i32 as i64),
+        (Encoding::Utf8, _) => return *last as u8 as i64,
+        (Encoding::Wide, _) if encoding.width() == 2 => return *last as u16 as i64,
+        (Encoding::Wide, _) => return *last as i32 as i64,
+        (Encoding::Utf16 | Encoding::Utf32, _) => return *last as i64,
+    }
+}
@@ -0,0 +1,147 @@
+//! # Intern
+//! The string interner. Every name in the program, like
+//! identifiers, macro names and string literals, is stored
+//! once and referred to by a `Symbol`, which is as cheap to
+//! copy, compare and hash as an integer.
+//!
+//! The interner is global to the process, so a `Symbol` can
+//! be resolved anywhere, even in another thread, without
+//! access to the source or to the interner. The strings are
+//! leaked on purpose, so that they live as long as the program
+//! and can be borrowed as `&'static str`.
+//!
+//! Interning takes a lock, but resolving a symbol does not:
+//! the strings are kept in append-only chunks that are only
+//! ever written once per slot, so reading them is just a couple
+//! of atomic loads. Symbols are resolved all the time, by the
+//! preprocessor above all, so this matters.
+
+#![allow(clippy::needless_return)]
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// The size of the first chunk, every other one being twice
+/// as big as the one before.
+const FIRST_CHUNK: usize = 1024;
+
+/// Enough chunks for every `u32`.
+const CHUNKS: usize = 23;
+
+static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+
+/// # Strings
+/// The interned strings, indexed by their `Symbol`, see
+/// `chunk()`. Only `Interner::intern()` writes them.
+static STRINGS: [OnceLock<Box<[OnceLock<&'static str>]>>; CHUNKS] = [const { OnceLock::new() }; CHUNKS];
+
+fn interner() -> MutexGuard<'static, Interner> {
+    let interner = INTERNER.get_or_init(|| Mutex::new(Interner::default()));
+    // The interner is always left consistent, even by a panic
+    return interner.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+}
+
+/// # Chunk
+/// Returns the chunk of `STRINGS` the string of the symbol
+/// `index` is in, and where in it.
+fn chunk(index: u32) -> (usize, usize) {
+    let index = index as usize + FIRST_CHUNK;
+    let chunk = (usize::BITS - index.leading_zeros() - 1) as usize - FIRST_CHUNK.trailing_zeros() as usize;
+    return (chunk, index - (FIRST_CHUNK << chunk));
+}
+
+#[derive(Default)]
+/// # Interner
+/// The symbols of the interned strings, and how many
+/// there are.
+struct Interner {
+    symbols: HashMap<&'static str, Symbol>,
+    len: u32,
+}
+
+impl Interner {
+    fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(string) {
+            return *symbol;
+        }
+        // Interned strings are never freed, like in most compilers
+        let string: &'static str = Box::leak(string.into());
+        let symbol = Symbol(self.len);
+        let (chunk, slot) = chunk(symbol.0);
+        let chunk = STRINGS[chunk].get_or_init(|| (0..FIRST_CHUNK << chunk).map(|_| OnceLock::new()).collect());
+        // The lock makes sure every slot is only written once
+        chunk[slot].set(string).unwrap();
+        self.len += 1;
+        self.symbols.insert(string, symbol);
+        return symbol;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// # Symbol
+/// A handle to an interned string. Two symbols are equal
+/// when their strings are, and they are ordered by when
+/// they were first interned, not alphabetically.
+///
+/// Symbols dereference to their string, so they can be
+/// used wherever a `&str` is expected.
+///
+/// ## Example
+/// ```ignore
+/// let name = Symbol::intern("main");
+/// assert!(name == Symbol::intern("main"));
+/// assert!(name.as_str() == "main");
+/// ```
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn intern(string: &str) -> Symbol {
+        return interner().intern(string);
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        // A symbol only exists once its string has been written
+        let (chunk, slot) = chunk(self.0);
+        return STRINGS[chunk].get().and_then(|chunk| chunk[slot].get()).unwrap();
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        return self.as_str();
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Symbol {
+        return Symbol::intern(string);
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(string: String) -> Symbol {
+        return Symbol::intern(&string);
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        return self.as_str() == other;
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{:?}", self.as_str());
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(self.as_str());
+    }
+}
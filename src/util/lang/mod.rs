@@ -0,0 +1,90 @@
+//! # Language
+//! The C standards supported by the compiler, selected
+//! with `-std=` on the command line, and the extensions
+//! to them.
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+/// # Standard
+/// A revision of the C standard. Revisions are ordered,
+/// so `standard >= Standard::C11` reads as "C11 or later".
+pub enum Standard {
+    /// ANSI C, also known as C90
+    C89,
+    #[default]
+    C99,
+    C11,
+    C17,
+    C23,
+}
+
+impl Standard {
+    /// # From Name
+    /// Parses the name given to `-std=`, accepting the
+    /// same spellings as GCC.
+    pub fn from_name(name: &str) -> Option<Standard> {
+        match name {
+            "c89" | "c90" | "ansi" | "iso9899:1990" | "gnu89" | "gnu90" => Some(Standard::C89),
+            "c99" | "c9x" | "iso9899:1999" | "gnu99" | "gnu9x" => Some(Standard::C99),
+            "c11" | "c1x" | "iso9899:2011" | "gnu11" | "gnu1x" => Some(Standard::C11),
+            "c17" | "c18" | "iso9899:2017" | "iso9899:2018" | "gnu17" | "gnu18" => Some(Standard::C17),
+            "c23" | "c2x" | "iso9899:2024" | "gnu23" | "gnu2x" => Some(Standard::C23),
+            _ => None
+        }
+    }
+
+    /// # Version
+    /// The value of `__STDC_VERSION__`, which C89 does
+    /// not define.
+    pub fn version(&self) -> Option<&'static str> {
+        match self {
+            Standard::C89 => None,
+            Standard::C99 => Some("199901L"),
+            Standard::C11 => Some("201112L"),
+            Standard::C17 => Some("201710L"),
+            Standard::C23 => Some("202311L"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// # Extensions
+/// The extensions to the standard that are enabled. Each
+/// of them adds some keywords, see `Lexer::set_extensions()`.
+pub struct Extensions {
+    /// ## GNU
+    /// GCC's extensions, enabled by the GNU standards
+    /// (`-std=gnu11`).
+    pub gnu: bool,
+    /// ## MSVC
+    /// Microsoft's extensions (`-fms-extensions`).
+    pub msvc: bool,
+}
+
+// The tables below are what `__has_attribute` and friends answer
+// with, so that headers only take the code paths the compiler can
+// handle. They must grow together with the parser, which does not
+// parse attributes, builtins or any declaration yet, so they are
+// deliberately empty: claiming `c_static_assert` or `[[nodiscard]]`
+// would make headers use code the compiler can't compile.
+
+/// # Attributes
+/// The GNU attributes (`__attribute__((name))`) the compiler
+/// understands, as reported by `__has_attribute`.
+pub const ATTRIBUTES: &[&str] = &[];
+
+/// # C Attributes
+/// The standard attributes (`[[name]]`) the compiler understands,
+/// with the value reported by `__has_c_attribute`: the date of the
+/// revision of the standard that introduced them, like `201904`
+/// for `deprecated`.
+pub const C_ATTRIBUTES: &[(&str, i64)] = &[];
+
+/// # Builtins
+/// The builtin functions (`__builtin_expect`...) the compiler
+/// understands, as reported by `__has_builtin`.
+pub const BUILTINS: &[&str] = &[];
+
+/// # Features
+/// The language features and extensions reported by clang's
+/// `__has_feature` and `__has_extension`, like `c_static_assert`.
+pub const FEATURES: &[&str] = &[];
@@ -1,3 +1,6 @@
 pub mod diag;
+pub mod lang;
 pub mod scan;
-pub mod num;
\ No newline at end of file
+pub mod num;
+pub mod escape;
+pub mod intern;
\ No newline at end of file
@@ -1,8 +1,10 @@
+#![allow(clippy::needless_return)]
+
 use crate::util::diag::err::CompilerError;
 
 use super::scan::Scanner;
 use super::diag::{Diagnostics, Status, Diagnostic, CompilerDiagnostic};
-use super::diag::{ok, error, warning};
+use super::diag::{ok, error};
 
 #[derive(Debug)]
 pub enum NumberType {
@@ -55,7 +57,7 @@ impl<'num> NumberParser<'num> {
         NumberParser {
             diag,
             numtype: NumberType::default(),
-            scan: Scanner::new(source, Some(b'\n')),
+            scan: Scanner::new(source),
         }
     }
 
@@ -67,7 +69,7 @@ impl<'num> NumberParser<'num> {
             Some(d) => d,
             None => return false // error
         };
-        self.numtype = NumberType::Int((self.numtype.int().unwrap() * base as i128  + conv_digit as i128).into());
+        self.numtype = NumberType::Int(self.numtype.int().unwrap() * base as i128  + conv_digit as i128);
         return true;
     }
 
@@ -83,25 +85,22 @@ impl<'num> NumberParser<'num> {
 
     // FIXME: refactor this please
     pub fn binary(&mut self) -> Status {
-        dbg!(*self.scan.peek(2).unwrap_or(&0) as char);
-        match self.scan.nth(1) {
+        match self.scan.next() {
             Some(digit @ (b'0' | b'1')) => if !self.push_int_digit(digit, 2) { return error!(CompilerError::MALFORMED_NUMBER); },
             Some(b' ' | b'\n' | b'\t') | None => return ok!(),
             Some(b'l' | b'L' | b'z' | b'Z' | b'u' | b'U') => return self.suffix(),
             _ => return error!(CompilerError::MALFORMED_NUMBER)
         }
-        self.scan.ptr -= 1;
         return self.binary()
     }
 
     pub fn octal(&mut self) -> Status {
-        match self.scan.nth(1) {
+        match self.scan.next() {
             Some(digit @ b'0' ..= b'7') => if !self.push_int_digit(digit, 8) { return error!(CompilerError::MALFORMED_NUMBER); },
             Some(b' ' | b'\n' | b'\t') | None => return ok!(),
             Some(b'l' | b'L' | b'z' | b'Z' | b'u' | b'U') => return self.suffix(),
             _ => return error!(CompilerError::MALFORMED_NUMBER)
         }
-        self.scan.ptr -= 1;
         return self.octal()
     }
 
@@ -117,7 +116,7 @@ impl<'num> NumberParser<'num> {
     }
 
     pub fn hex(&mut self) -> Status {
-        match self.scan.nth(1) {
+        match self.scan.next() {
             Some(digit @ (b'0' ..= b'9'
                 | b'A' ..= b'F'
                 | b'a' ..= b'f')) => if !self.push_int_digit(digit, 16) { return error!(CompilerError::MALFORMED_NUMBER); },
@@ -125,7 +124,6 @@ impl<'num> NumberParser<'num> {
             Some(b'l' | b'L' | b'z' | b'Z' | b'u' | b'U') => return self.suffix(),
             _ => return error!(CompilerError::MALFORMED_NUMBER)
         }
-        self.scan.ptr -= 1;
         return self.hex()
     }
 
@@ -135,7 +133,7 @@ impl<'num> NumberParser<'num> {
             Some(b'0') => match (self.scan.peek(1), self.scan.peek(2)) {
                 // 0xxNUM
                 (Some(b'x' | b'X'), Some(b'x' | b'X')) => {
-                    self.scan.next(); // advance by one since octal() advances by another automatically
+                    self.scan.nth(1); // skip the prefix, octal() reads from the next item on
                     return self.octal();
                 },
 
@@ -146,13 +144,22 @@ impl<'num> NumberParser<'num> {
                 (Some(b'6'), Some(b'6')) => return self.octal(),
 
                 // 0xNUM
-                (Some(b'x' | b'X'), _) => return self.hex(),
+                (Some(b'x' | b'X'), _) => {
+                    self.scan.next();
+                    return self.hex();
+                },
                 // PLACEHOLDER FALSE! Only if a RUNTIME FEATURE (in this case an extension) IS TURNED ON!
                 // NO CURRENT SUPPORT OF RUNTIME FEATURES!
                 // 0oNUM
-                (Some(b'o' | b'O'), _) if false => return self.octal(),
+                (Some(b'o' | b'O'), _) if false => {
+                    self.scan.next();
+                    return self.octal();
+                },
                 // 0bNUM
-                (Some(b'b' | b'B'), _) => return self.binary(), // same as down here, 0b is an extension
+                (Some(b'b' | b'B'), _) => { // same as down here, 0b is an extension
+                    self.scan.next();
+                    return self.binary();
+                },
 
                 // 0NUM
                 (Some(b'0' ..= b'9'), _) => return self.octal(),
@@ -169,16 +176,12 @@ impl<'num> NumberParser<'num> {
     }
 
     pub fn num(&mut self) {
-        match self.init() {
-            Some(ref stat) => {
-                self.diag.push(Diagnostic { diagnostic: stat.clone(), location: self.scan.location });
-                match stat {
-                    CompilerDiagnostic::Error(_) => (),
-                    CompilerDiagnostic::Warning(_) => return self.num(),
-                }
+        if let Some(ref stat) = self.init() {
+            self.diag.push(Diagnostic::new(stat.clone(), self.scan.location));
+            match stat {
+                CompilerDiagnostic::Error(_) => (),
+                CompilerDiagnostic::Warning(_) => return self.num(),
             }
-            
-            None => ()
         }
     }
 
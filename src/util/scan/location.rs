@@ -1,14 +1,16 @@
+#![allow(clippy::needless_return)]
+
+use super::source::FileId;
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd,)]
 /// # Location
 /// The Location struct is a utility struct that represents the
-/// location of any object in a source file, using only position
-/// and line.
-// TODO: add column count..? I am pretty sure it can be calculated,
-// however..
+/// location of any object in a source file, as the file and the
+/// byte offset in it. Lines and columns are computed from the
+/// offset by the `SourceManager`.
 pub struct Location {
-    pub position: usize,
-    pub column: usize,
-    pub line: usize,
+    pub file: FileId,
+    pub offset: usize,
 }
 
 
@@ -16,4 +18,27 @@ impl Location {
     pub fn new() -> Location {
         return Self::default();
     }
-}
\ No newline at end of file
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd,)]
+/// # Span
+/// The bytes from `start` up to `end` of a file, like the
+/// source of a token.
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// # At
+    /// An empty span at `location`, for what has no
+    /// source of its own.
+    pub fn at(location: Location) -> Span {
+        return Span { file: location.file, start: location.offset, end: location.offset };
+    }
+
+    pub fn location(&self) -> Location {
+        return Location { file: self.file, offset: self.start };
+    }
+}
@@ -1,8 +1,11 @@
+#![allow(clippy::needless_return)]
+
 use std::cmp::PartialEq;
 use std::array;
 
 pub mod location;
 pub mod buffer;
+pub mod source;
 
 use location::*;
 use buffer::*;
@@ -16,15 +19,15 @@ use buffer::*;
 /// configurable lookahead, like Lexing, Parsing and
 /// so on.
 /// 
-/// A newline of type `T` can be provided in order
-/// to tack the line count in the `location` field.
+/// The `location` field holds the offset of the
+/// current item, in the file set by the owner.
 /// 
 /// ## Example
 /// ```ignore
 /// let col = b"My tiny collection";
 /// // Lookahead of 4, so the method `self.peek(n)` can access the next
 /// // four items, ranging from n = 0 to n = 3.
-/// let mut scan: Scanner<u8, 4> = Scanner::new(col, b'\n');
+/// let mut scan: Scanner<u8, 4> = Scanner::new(col);
 /// assert!(scan.peek(0) == Some(&b'M')); // current item
 /// assert!(scan.peek(1) == Some(&b'y')); // next item
 /// assert!(scan.peek(2) == Some(&b' ')); // 2nd next item
@@ -33,7 +36,10 @@ use buffer::*;
 /// ```
 /// 
 /// Since `Scanner` implements `Iterator`, the former can be advanced
-/// with `self.next()`:
+/// with `self.next()`, which moves to the next item and returns it,
+/// so that it becomes `peek(0)`. `ptr` and `location` are always
+/// the index of the current item, and `peek_back(n)` is the item
+/// `n` places before it:
 /// ```ignore
 /// assert!(scan.next() == Some(&b'y'));
 /// assert!(scan.peek(1) == Some(&b' ')); // next item
@@ -54,23 +60,17 @@ pub struct Scanner<'scan, T: PartialEq, const L: usize> {
     /// previous values. Also `L` size just like
     /// the `lookahead`.
     pub lookback:  [Option<&'scan T>; L],
-    /// ## Newline
-    /// The Newline is the item `T` that when
-    /// encountered it raises the `Location` line
-    /// count and resets the column count.
-    pub newline: Option<T>,
     pub location: Location,
     pub buffer: Option<Buffer>,
 }
 
 impl<'scan, T: PartialEq, const L: usize> Scanner<'scan, T, L> {
-    pub fn new(item_collection: &'scan [T], newline: Option<T>) -> Scanner<'scan, T, L> {
+    pub fn new(item_collection: &'scan [T]) -> Scanner<'scan, T, L> {
         return Scanner {
             item_collection,
             ptr: 0,
             lookahead: array::from_fn(|i| item_collection.get(i)),
-            lookback: array::from_fn(|i| item_collection.get(i)),
-            newline,
+            lookback: array::from_fn(|i| if i == 0 { item_collection.first() } else { None }),
             location: Location::default(),
             buffer: None,
         }
@@ -88,15 +88,6 @@ impl<'scan, T: PartialEq, const L: usize> Scanner<'scan, T, L> {
         return self.lookback.get(n).copied().flatten();
     }
 
-    pub fn increment_location(&mut self) {
-        self.location.position += 1;
-        self.location.column += 1;
-        if self.newline != None && self.peek(0) == self.newline.as_ref() {
-            self.location.line += 1;
-            self.location.column = 0;
-        }
-    }
-
     pub fn push_to_buffer(&mut self) {
         if let Some(ref mut buf) = self.buffer {
             buf.size += 1;
@@ -108,14 +99,12 @@ impl<'scan, T: PartialEq, const L: usize> Scanner<'scan, T, L> {
     }
 
     pub fn get_from_buffer(&mut self) -> Option<&'scan [T]> {
-        let Some(ref buf) = self.buffer else { return None; };
+        let buf = self.buffer.as_ref()?;
         return self.item_collection.get(buf.start..buf.start + buf.size);
     }
 
     pub fn reset_buffer(&mut self) {
-        let mut buf = Buffer::default();
-        buf.start = self.location.position;
-        self.buffer = Some(buf);
+        self.buffer = Some(Buffer { start: self.location.offset, ..Default::default() });
     }
 }
 
@@ -123,15 +112,13 @@ impl<'scan, T: PartialEq,  const L: usize> Iterator for Scanner<'scan, T, L> {
     type Item = &'scan T;
 
     fn next(&mut self) -> Option<Self::Item> {
+        // `ptr` always points at the current item, so that after advancing
+        // `peek(0)` returns the very same item `next()` just returned.
         self.ptr += 1;
-        self.location.position = self.ptr - 1;
-        let next_item = self.item_collection.get(self.ptr - 1);
-        self.lookahead = array::from_fn(|i| self.item_collection.get(self.ptr - 1 + i));
-        self.lookback  = array::from_fn(|i| self.item_collection.get(self.ptr.saturating_sub(i).saturating_sub(1)));
-        match next_item {
-            Some(nl) if Some(nl) == self.newline.as_ref() => self.location.line += 1,
-            _ => {}
-        }
+        self.location.offset = self.ptr;
+        let next_item = self.item_collection.get(self.ptr);
+        self.lookahead = array::from_fn(|i| self.item_collection.get(self.ptr + i));
+        self.lookback  = array::from_fn(|i| self.ptr.checked_sub(i).and_then(|p| self.item_collection.get(p)));
         return next_item;
     }
 }
\ No newline at end of file
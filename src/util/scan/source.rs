@@ -0,0 +1,99 @@
+#![allow(clippy::needless_return)]
+
+use std::cell::OnceCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::location::Location;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// # FileId
+/// A handle to a file loaded by a `SourceManager`.
+/// The first file loaded, usually the main source
+/// file, is also the default one.
+pub struct FileId(u32);
+
+/// # Source File
+/// A file's path and contents, together with the
+/// offsets its lines start at, which are only
+/// computed once a line number is asked for.
+struct SourceFile {
+    path: PathBuf,
+    source: Rc<[u8]>,
+    lines: OnceCell<Vec<usize>>,
+}
+
+impl SourceFile {
+    fn lines(&self) -> &[usize] {
+        return self.lines.get_or_init(|| {
+            let newlines = self.source.iter().enumerate().filter(|(_, c)| **c == b'\n');
+            return [0].into_iter().chain(newlines.map(|(i, _)| i + 1)).collect();
+        });
+    }
+}
+
+#[derive(Default)]
+/// # Source Manager
+/// Owns every file read while compiling and hands out
+/// a `FileId` for each of them, so that a `Location`
+/// only needs the id and a byte offset. Lines and
+/// columns are computed from the offset when needed.
+///
+/// ## Example
+/// ```ignore
+/// let mut sources = SourceManager::new();
+/// let file = sources.add("main.c", "int x;\nint y;");
+/// let location = Location { file, offset: 11 };
+/// assert!(sources.line(location) == 1); // counted from 0
+/// assert!(sources.column(location) == 4);
+/// ```
+pub struct SourceManager {
+    files: Vec<SourceFile>,
+}
+
+impl SourceManager {
+    pub fn new() -> SourceManager {
+        return SourceManager::default();
+    }
+
+    /// # Add
+    /// Adds a file that does not have to be read, like
+    /// the `<command-line>` one.
+    pub fn add(&mut self, path: impl Into<PathBuf>, source: impl Into<Rc<[u8]>>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile { path: path.into(), source: source.into(), lines: OnceCell::new() });
+        return id;
+    }
+
+    /// # Load
+    /// Reads the file at `path`.
+    pub fn load(&mut self, path: &Path) -> io::Result<FileId> {
+        let source = fs::read(path)?;
+        return Ok(self.add(path, source));
+    }
+
+    pub fn path(&self, file: FileId) -> &Path {
+        return &self.files[file.0 as usize].path;
+    }
+
+    pub fn source(&self, file: FileId) -> &[u8] {
+        return &self.files[file.0 as usize].source;
+    }
+
+    /// # Line
+    /// The line of `location` in its file, counted from 0.
+    pub fn line(&self, location: Location) -> usize {
+        let lines = self.files[location.file.0 as usize].lines();
+        return lines.partition_point(|start| *start <= location.offset) - 1;
+    }
+
+    /// # Column
+    /// The column of `location` in its line, counted from 0
+    /// in bytes.
+    pub fn column(&self, location: Location) -> usize {
+        let lines = self.files[location.file.0 as usize].lines();
+        return location.offset - lines[self.line(location)];
+    }
+}
@@ -0,0 +1,84 @@
+//! # Builtins
+//! The macros defined by the compiler itself, either plain
+//! object-like ones or ones like `__LINE__` that expand to
+//! something different every time.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static EXPANSIONS: &[(&str, &str)] = &[
+    ("__FILE__", "\"test.c\""),
+    ("__LINE__", "1"),
+    ("\n\n__LINE__", "3"),
+    ("a \\\n b __LINE__", "a b 2"),
+    ("/*\n\n*/ __LINE__", "3"),
+    ("#define L __LINE__\n\nL", "3"),
+    ("#define F(x) x\nF(\n__LINE__\n)", "3"),
+    ("__COUNTER__ __COUNTER__ __COUNTER__", "0 1 2"),
+    ("#define C __COUNTER__\nC C", "0 1"),
+    ("__STDC__ __STDC_HOSTED__", "1 1"),
+    ("__STDC_UTF_16__ __STDC_UTF_32__", "1 1"),
+    ("#define S(x) #x\n#define XS(x) S(x)\nXS(__LINE__) S(__LINE__)", "\"3\" \"__LINE__\""),
+    ("#if __LINE__ == 1 && __COUNTER__ == 0\nyes\n#endif", "yes"),
+    ("#ifdef __FILE__\nyes\n#endif", "yes"),
+];
+
+static VERSIONS: &[(Standard, &str)] = &[
+    (Standard::C89, "__STDC_VERSION__"),
+    (Standard::C99, "199901L"),
+    (Standard::C11, "201112L"),
+    (Standard::C17, "201710L"),
+    (Standard::C23, "202311L"),
+];
+
+static REDEFINED: &[&str] = &[
+    "#undef __LINE__\n",
+    "#undef __FILE__\n",
+    "#define __STDC__ 2\n",
+    "#define __COUNTER__ 0\n",
+    "#undef __DATE__\n",
+];
+
+#[test]
+fn expansions() {
+    for (source, expected) in EXPANSIONS {
+        assert_eq!(common::preprocess(source, &Options::default()), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn stdc_version() {
+    for (standard, expected) in VERSIONS {
+        assert_eq!(common::preprocess("__STDC_VERSION__", &Options::standard(*standard)), (expected.to_string(), vec![]));
+    }
+}
+
+#[test]
+fn unsupported_features() {
+    let source = "#if defined __STDC_NO_VLA__ && defined __STDC_NO_ATOMICS__\nyes\n#else\nno\n#endif";
+    assert_eq!(common::branch(source, &Options::standard(Standard::C99)), (Some(false), vec![]));
+    assert_eq!(common::branch(source, &Options::standard(Standard::C11)), (Some(true), vec![]));
+}
+
+#[test]
+fn redefined() {
+    for source in REDEFINED {
+        let (_, diagnostics) = common::preprocess(source, &Options::default());
+        assert_eq!(diagnostics, ["warning: undefining or redefining builtin macro"], "{source:?}");
+    }
+    // The ones describing the target can be changed freely
+    let (text, diagnostics) = common::preprocess("#undef __CHAR_BIT__\n#define __CHAR_BIT__ 16\n__CHAR_BIT__", &Options::default());
+    assert_eq!((text.as_str(), diagnostics), ("16", vec![]));
+}
+
+#[test]
+fn source_date_epoch() {
+    std::env::set_var("SOURCE_DATE_EPOCH", "0");
+    let expanded = common::preprocess("__DATE__ __TIME__", &Options::default());
+    std::env::remove_var("SOURCE_DATE_EPOCH");
+    assert_eq!(expanded, ("\"Jan  1 1970\" \"00:00:00\"".to_string(), vec![]));
+}
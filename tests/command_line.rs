@@ -0,0 +1,88 @@
+//! # Command Line
+//! `-D`, `-U`, `-imacros` and `-include` are processed before
+//! the first line of the source, as if they were directives.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::{Options, TempDir};
+use tornado_cc::front::cpp::Preprocessor;
+
+static DEFINITIONS: &[(&[&str], &str, &str)] = &[
+    (&["-DX"], "X", "1"),
+    (&["-DX=2"], "X", "2"),
+    (&["-DX="], "[X]", "[]"),
+    (&["-DX=a=b"], "X", "a=b"),
+    (&["-DF(x)=x+x"], "F(1)", "1+1"),
+    (&["-DX=1", "-UX"], "X", "X"),
+    (&["-UX", "-DX=1"], "X", "1"),
+    (&["-DX=1\nignored"], "X", "1"),
+    (&["-DX=Y", "-DY=2"], "X", "2"),
+    (&["-DX=1"], "#ifdef X\nyes\n#endif", "yes"),
+    (&["-DX=1"], "#undef X\nX", "X"),
+];
+
+fn apply(cpp: &mut Preprocessor, args: &[&str]) {
+    for arg in args {
+        match arg.split_at(2) {
+            ("-D", definition) => cpp.define_macro(definition),
+            ("-U", name) => cpp.undefine_macro(name),
+            _ => panic!("unknown option {arg}")
+        }
+    }
+}
+
+#[test]
+fn definitions() {
+    for (args, source, expected) in DEFINITIONS {
+        let expanded = common::preprocess_with(source, &Options::default(), |cpp| apply(cpp, args));
+        assert_eq!(expanded, (expected.to_string(), vec![]), "{args:?}");
+    }
+}
+
+#[test]
+fn redefinitions() {
+    let (_, diagnostics) = common::preprocess_with("", &Options::default(), |cpp| apply(cpp, &["-U__LINE__"]));
+    assert_eq!(diagnostics, ["warning: undefining or redefining builtin macro"]);
+    let (_, diagnostics) = common::preprocess_with("#define X 2\n", &Options::default(), |cpp| apply(cpp, &["-DX=1"]));
+    assert_eq!(diagnostics, ["warning: macro redefined"]);
+    let (_, diagnostics) = common::preprocess_with("", &Options::default(), |cpp| apply(cpp, &["-DX=1", "-DX=2"]));
+    assert_eq!(diagnostics, ["warning: macro redefined"]);
+}
+
+#[test]
+fn files() {
+    let dir = TempDir::new("command-line", &[
+        ("config.h", "#define CONFIG 1\nconfig\n"),
+        ("macros.h", "#define MACROS CONFIG\nmacros\n#pragma pack(1)\n"),
+        ("file.h", "__FILE__\n"),
+    ]);
+    let path = |file: &str| dir.path.join(file).to_string_lossy().into_owned();
+    let options = Options::path(&dir.path.join("test.c"));
+
+    let included = common::preprocess_with("main CONFIG", &options, |cpp| cpp.include_file(&path("config.h")));
+    assert_eq!(included, ("config\nmain 1".to_string(), vec![]));
+
+    let macros = common::preprocess_with("MACROS", &options, |cpp| cpp.include_macros(&path("macros.h")));
+    assert_eq!(macros, ("CONFIG".to_string(), vec![]));
+
+    // -imacros goes before -include, whatever the order they are given in
+    let both = common::preprocess_with("MACROS", &options, |cpp| {
+        cpp.include_file(&path("config.h"));
+        cpp.include_macros(&path("macros.h"));
+    });
+    assert_eq!(both, ("config\n1".to_string(), vec![]));
+
+    let file = common::preprocess_with("", &options, |cpp| cpp.include_file(&path("file.h")));
+    assert_eq!(file, (format!("\"{}\"", path("file.h")), vec![]));
+
+    let (_, diagnostics) = common::preprocess_with("", &options, |cpp| cpp.include_file(&path("missing.h")));
+    assert_eq!(diagnostics, [format!("error: '{}' file not found", path("missing.h"))]);
+}
+
+#[test]
+fn file_and_line_after_the_command_line() {
+    let expanded = common::preprocess_with("__FILE__ __LINE__", &Options::default(), |cpp| apply(cpp, &["-DX", "-DY"]));
+    assert_eq!(expanded, ("\"test.c\" 1".to_string(), vec![]));
+}
@@ -0,0 +1,124 @@
+//! # Common
+//! What the tests of the preprocessor share. Not every test
+//! uses all of it.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use tornado_cc::front::cpp::{Preprocessor, SearchPaths};
+use tornado_cc::front::lex::Lexer;
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::lang::Standard;
+use tornado_cc::util::scan::source::SourceManager;
+
+/// # Options
+/// How a test source is preprocessed: as the file at `path`,
+/// which does not need to exist, in `standard`, with the
+/// include directories of `search` and with or without
+/// `trigraphs`. With `finish`, the tokens are made ready for
+/// the parser, like without `-E`, and with `linemarkers` the
+/// output is printed like `-E` instead of `-E -P`.
+pub struct Options {
+    pub path: PathBuf,
+    pub standard: Standard,
+    pub search: SearchPaths,
+    pub trigraphs: bool,
+    pub finish: bool,
+    pub linemarkers: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        return Options { path: PathBuf::from("test.c"), standard: Standard::default(), search: SearchPaths::default(), trigraphs: false, finish: false, linemarkers: false };
+    }
+}
+
+impl Options {
+    pub fn standard(standard: Standard) -> Options {
+        return Options { standard, ..Options::default() };
+    }
+
+    pub fn path(path: &Path) -> Options {
+        return Options { path: path.to_path_buf(), ..Options::default() };
+    }
+}
+
+/// # Preprocess
+/// Preprocesses `source` with `options`. Returns the output,
+/// trimmed, and the diagnostics, like `error: ...`.
+pub fn preprocess(source: &str, options: &Options) -> (String, Vec<String>) {
+    return preprocess_with(source, options, |_| ());
+}
+
+/// # Preprocess With
+/// Like `preprocess()`, but `setup` gets the preprocessor right
+/// before it starts, for what `options` can't do.
+pub fn preprocess_with(source: &str, options: &Options, setup: impl FnOnce(&mut Preprocessor)) -> (String, Vec<String>) {
+    let mut sources = SourceManager::new();
+    let file = sources.add(&options.path, source.as_bytes());
+    let mut diag = Diagnostics::new();
+    let mut lexer = Lexer::new(sources.source(file), file, &mut diag);
+    lexer.set_standard(options.standard);
+    lexer.set_trigraphs(options.trigraphs);
+    let tokens = lexer.collect();
+    let mut preprocessor = Preprocessor::new(file, tokens, &mut sources, &mut diag);
+    preprocessor.set_standard(options.standard);
+    preprocessor.set_trigraphs(options.trigraphs);
+    *preprocessor.get_search_paths() = options.search.clone();
+    setup(&mut preprocessor);
+    preprocessor.preprocess();
+    if options.finish {
+        preprocessor.finish();
+    }
+    let text = preprocessor.print(options.linemarkers);
+    let diagnostics = diag.iter().map(|diagnostic| diagnostic.diagnostic.to_string()).collect();
+    return (text.trim().to_owned(), diagnostics);
+}
+
+/// # Branch
+/// Preprocesses `source`, which should output either `yes`
+/// or `no`. Returns which one, or nothing if it is neither,
+/// and the diagnostics.
+pub fn branch(source: &str, options: &Options) -> (Option<bool>, Vec<String>) {
+    let (text, diagnostics) = preprocess(source, options);
+    match text.as_str() {
+        "yes" => return (Some(true), diagnostics),
+        "no" => return (Some(false), diagnostics),
+        _ => return (None, diagnostics)
+    }
+}
+
+/// # Evaluate
+/// Evaluates `condition` in an `#if` coming after `prelude`,
+/// see `branch()`.
+pub fn evaluate(prelude: &str, condition: &str, options: &Options) -> (Option<bool>, Vec<String>) {
+    return branch(&format!("{prelude}#if {condition}\nyes\n#else\nno\n#endif\n"), options);
+}
+
+/// # Temp Dir
+/// A directory with some files in it, for the tests that
+/// include or embed them. It is removed when dropped.
+pub struct TempDir {
+    pub path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates the directory `name`, unique to the test run, with
+    /// the `files`, given as paths relative to it and contents.
+    pub fn new(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let path = std::env::temp_dir().join(format!("tornado-{name}-{}", std::process::id()));
+        for (file, contents) in files {
+            let file = path.join(file);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, contents).unwrap();
+        }
+        return TempDir { path };
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,155 @@
+//! # Conditions
+//! The expressions of `#if` are evaluated like C99 6.10.1
+//! says: in `intmax_t` and `uintmax_t`, with `&&`, `||` and
+//! `?:` short-circuiting, character constants as `int`, and
+//! identifiers that are not macros as 0.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static SHORT_CIRCUIT: &[(&str, bool)] = &[
+    ("0 && 1 / 0", false),
+    ("1 || 1 / 0", true),
+    ("0 && (1 % 0)", false),
+    ("0 ? 1 / 0 : 2", true),
+    ("1 ? 2 : 1 / 0", true),
+    ("1 || 0 && 1 / 0", true),
+    ("(0 && 1 / 0) || 1", true),
+];
+
+static SIGNEDNESS: &[(&str, bool)] = &[
+    ("-1 < 0", true),
+    ("-1 < 0u", false),
+    ("-1 > 0U", true),
+    ("0xFFFFFFFFFFFFFFFF == -1", true),
+    ("18446744073709551615 > 0", true),
+    ("9223372036854775807 > 0", true),
+    ("(1 ? -1 : 0u) > 0", true),
+    ("(1 ? -1 : 0) < 0", true),
+    ("-1 / 2 == 0", true),
+    ("-7 % 2 == -1", true),
+    ("-1 >> 1 == -1", true),
+    ("-1u >> 63 == 1", true),
+    ("~0u == 0xFFFFFFFFFFFFFFFF", true),
+    ("(6 & 3) == 2 && (6 | 3) == 7 && (6 ^ 3) == 5", true),
+    ("1 << 3 == 8 && 256 >> 4 == 16", true),
+    ("(2 || 0) == 1 && (2 && 3) == 1 && !5 == 0", true),
+];
+
+static CHAR_CONSTANTS: &[(&str, bool)] = &[
+    ("'a' == 97", true),
+    ("'\\n' == 10 && '\\0' == 0", true),
+    ("'\\x41' == 'A' && '\\101' == 'A'", true),
+    ("'\\377' < 0", true),
+    ("'\\'' == 39 && '\"' == 34", true),
+    ("u'\\xFFFF' == 65535 && U'\\x10FFFF' == 0x10FFFF", true),
+    ("L'a' == 'a'", true),
+];
+
+static DEFINED: &[(&str, bool)] = &[
+    ("defined EMPTY", true),
+    ("defined(ZERO) && !ZERO", true),
+    ("defined UNDEFINED", false),
+    ("!defined(UNDEFINED) && UNDEFINED == 0", true),
+    ("DEFINED_EMPTY", true),
+    ("defined __FILE__ && defined __LINE__", true),
+    ("defined(defined_later)", false),
+];
+
+static SUFFIXES: &[(&str, bool)] = &[
+    ("1u == 1 && 1U == 1 && 1l == 1 && 1L == 1", true),
+    ("1ll == 1 && 1LL == 1 && 1ull == 1 && 1LLU == 1", true),
+    ("1lu == 1 && 1uLL == 1 && 0x1Fu == 31 && 0b1l == 1", true),
+    ("-1 < 0ul", false),
+];
+
+static INVALID_SUFFIXES: &[(&str, &str)] = &[
+    ("1xyz", "xyz"),
+    ("1uu", "uu"),
+    ("1lL", "lL"),
+    ("1lll", "lll"),
+    ("1ulu", "ulu"),
+    ("0x1g", "g"),
+    ("1_", "_"),
+];
+
+static MALFORMED: &[&str] = &["1 +", "(1", "1)", "defined", "defined(", "defined(EMPTY", "1 ? 2", ""];
+
+/// Evaluates `condition` in an `#if`, after the definitions
+/// of `DEFINED`, returning its value and diagnostics.
+fn evaluate_in(condition: &str, standard: Standard) -> (Option<bool>, Vec<String>) {
+    let source = format!("#define EMPTY\n#define ZERO 0\n#define DEFINED_EMPTY defined EMPTY\n\
+        #if {condition}\nyes\n#else\nno\n#endif\n#define defined_later\n");
+    return common::branch(&source, &Options::standard(standard));
+}
+
+fn evaluate(condition: &str) -> (Option<bool>, Vec<String>) {
+    return evaluate_in(condition, Standard::default());
+}
+
+fn check(cases: &[(&str, bool)]) {
+    for (condition, expected) in cases {
+        assert_eq!(evaluate(condition), (Some(*expected), vec![]), "{condition:?}");
+    }
+}
+
+#[test]
+fn short_circuit() {
+    check(SHORT_CIRCUIT);
+}
+
+#[test]
+fn evaluated_division_by_zero() {
+    for condition in ["1 / 0", "1 && 1 / 0", "0 || 1 % 0", "1 ? 1 / 0 : 0"] {
+        let (_, diagnostics) = evaluate(condition);
+        assert_eq!(diagnostics, ["error: division by zero in preprocessor expression"], "{condition:?}");
+    }
+}
+
+#[test]
+fn signed_and_unsigned() {
+    check(SIGNEDNESS);
+}
+
+#[test]
+fn suffixes() {
+    check(SUFFIXES);
+    assert_eq!(evaluate_in("1wb == 1 && 1uwb == 1 && 1WBU == 1", Standard::C23), (Some(true), vec![]));
+}
+
+#[test]
+fn invalid_suffixes() {
+    for (condition, suffix) in INVALID_SUFFIXES {
+        let (_, diagnostics) = evaluate(condition);
+        assert_eq!(diagnostics, [format!("error: invalid suffix '{suffix}' on integer constant")], "{condition:?}");
+    }
+}
+
+#[test]
+fn char_constants() {
+    check(CHAR_CONSTANTS);
+}
+
+#[test]
+fn defined() {
+    check(DEFINED);
+}
+
+#[test]
+fn true_and_false() {
+    assert_eq!(evaluate_in("true && !false", Standard::C23), (Some(true), vec![]));
+    assert_eq!(evaluate_in("true || false", Standard::C99), (Some(false), vec![]));
+}
+
+#[test]
+fn malformed() {
+    for condition in MALFORMED {
+        let (_, diagnostics) = evaluate(condition);
+        assert_eq!(diagnostics.len(), 1, "{condition:?} gives {diagnostics:?}");
+        assert!(diagnostics[0].starts_with("error:"), "{condition:?} gives {diagnostics:?}");
+    }
+}
@@ -0,0 +1,62 @@
+//! # Depend
+//! `-M` and friends write the files the preprocessor opened as
+//! a Make rule, like GCC does. The tests run the driver in a
+//! temporary directory, so that the paths are short and known.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::process::Command;
+
+use common::TempDir;
+
+static FILES: &[(&str, &str)] = &[
+    ("main.c", "#include \"a.h\"\n#include <s.h>\n#include \"b.h\"\n#include \"a.h\"\n#embed \"data bin\"\n"),
+    ("a.h", "#include \"b.h\"\n"),
+    ("b.h", "b\n"),
+    ("sys/s.h", "s\n"),
+    ("data bin", "xy"),
+    ("long.c", "#include \"a_rather_long_header_name.h\"\n#include \"another_rather_long_header_name.h\"\n"),
+    ("a_rather_long_header_name.h", ""),
+    ("another_rather_long_header_name.h", ""),
+];
+
+static RULES: &[(&[&str], &str)] = &[
+    (&["-M", "main.c"], "main.o: main.c a.h b.h sys/s.h data\\ bin\n"),
+    (&["-MM", "main.c"], "main.o: main.c a.h b.h data\\ bin\n"),
+    (&["-M", "-MT", "out/main.o", "main.c"], "out/main.o: main.c a.h b.h sys/s.h data\\ bin\n"),
+    (&["-MM", "-MTa.o", "-MT", "b.o", "main.c"], "a.o b.o: main.c a.h b.h data\\ bin\n"),
+    (&["-MM", "-MP", "main.c"], "main.o: main.c a.h b.h data\\ bin\n\na.h:\n\nb.h:\n\ndata\\ bin:\n"),
+    (&["-M", "long.c"], "long.o: long.c a_rather_long_header_name.h \\\n another_rather_long_header_name.h\n"),
+];
+
+fn run(dir: &TempDir, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_tornado-cc"))
+        .args(["-nostdinc", "-isystem", "sys"])
+        .args(args)
+        .current_dir(&dir.path)
+        .output()
+        .unwrap();
+    return String::from_utf8_lossy(&output.stdout).into_owned();
+}
+
+#[test]
+fn rules() {
+    let dir = TempDir::new("depend-rules", FILES);
+    for (args, expected) in RULES {
+        assert_eq!(run(&dir, args), *expected, "{args:?}");
+    }
+}
+
+#[test]
+fn depfile_next_to_the_output() {
+    let dir = TempDir::new("depend-files", FILES);
+    // -MD goes on preprocessing, and writes the rule to a file
+    assert_eq!(run(&dir, &["-E", "-P", "-MD", "main.c"]), "b\ns\nb\nb\n120,121\n");
+    assert_eq!(std::fs::read_to_string(dir.path.join("main.d")).unwrap(), "main.o: main.c a.h b.h sys/s.h data\\ bin\n");
+    assert_eq!(run(&dir, &["-M", "-MF", "deps.d", "main.c"]), "");
+    assert_eq!(std::fs::read_to_string(dir.path.join("deps.d")).unwrap(), "main.o: main.c a.h b.h sys/s.h data\\ bin\n");
+    run(&dir, &["-E", "-MMD", "-MFmm.d", "main.c"]);
+    assert_eq!(std::fs::read_to_string(dir.path.join("mm.d")).unwrap(), "main.o: main.c a.h b.h data\\ bin\n");
+}
@@ -0,0 +1,79 @@
+//! # Directives
+//! `#line` renumbers the lines, `#error` and `#warning` report
+//! the rest of their line as written, and directives nobody
+//! knows are errors, unless they are skipped.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+
+static LINE: &[(&str, &str)] = &[
+    ("#line 10\n__LINE__", "10"),
+    ("#line 10\n\n\n__LINE__", "12"),
+    ("#line 10 \"other.c\"\n__FILE__ __LINE__", "\"other.c\" 10"),
+    ("#line 1 \"a\\\\b.c\"\n__FILE__", "\"a\\\\b.c\""),
+    ("#define N 20\n#define F \"f.c\"\n#line N F\n__LINE__ __FILE__", "20 \"f.c\""),
+    ("#line 2147483647\n__LINE__", "2147483647"),
+    ("#line 5\n#line 50\n__LINE__", "50"),
+    ("#line 10 \"x.c\"\n#line 20\n__FILE__", "\"x.c\""),
+    ("# 33 \"marker.c\" 1 3\n__LINE__ __FILE__", "33 \"marker.c\""),
+];
+
+static MALFORMED_LINE: &[&str] = &[
+    "#line\n",
+    "#line 0\n",
+    "#line 2147483648\n",
+    "#line -1\n",
+    "#line 0x10\n",
+    "#line 10 file.c\n",
+    "#line 10 \"x.c\" 1\n",
+    "#line \"x.c\"\n",
+];
+
+static MESSAGES: &[(&str, &str)] = &[
+    ("#error\n", "error: #error "),
+    ("#error stop here\n", "error: #error stop here"),
+    ("#error  spaced   out  \n", "error: #error spaced out"),
+    ("#error \"quoted\" 'c' (x)\n", "error: #error \"quoted\" 'c' (x)"),
+    ("#define X 1\n#error X is not expanded\n", "error: #error X is not expanded"),
+    ("#error a /* comment */ b // comment\n", "error: #error a b"),
+    ("#error don't stop\n", "error: #error don't stop"),
+    ("#warning careful\n", "warning: #warning careful"),
+    ("#\n", "warning: missing preprocessor directive"),
+    ("#unknown\n", "error: unknown preprocessor directive"),
+    ("#defined X\n", "error: unknown preprocessor directive"),
+    ("# 1x\n", "error: #line must be followed by a line number and an optional file name"),
+];
+
+#[test]
+fn line() {
+    for (source, expected) in LINE {
+        assert_eq!(common::preprocess(source, &Options::default()), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn malformed_line() {
+    let expected = "error: #line must be followed by a line number and an optional file name";
+    for source in MALFORMED_LINE {
+        let (_, diagnostics) = common::preprocess(source, &Options::default());
+        assert_eq!(diagnostics, [expected], "{source:?}");
+    }
+}
+
+#[test]
+fn messages() {
+    for (source, expected) in MESSAGES {
+        let (_, diagnostics) = common::preprocess(source, &Options::default());
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
+
+#[test]
+fn output_goes_on() {
+    let (text, diagnostics) = common::preprocess("a\n#error first\n#warning second\n#bogus\nb", &Options::default());
+    assert_eq!(text, "a\nb");
+    assert_eq!(diagnostics, ["error: #error first", "warning: #warning second", "error: unknown preprocessor directive"]);
+}
@@ -0,0 +1,72 @@
+//! # Groups
+//! Only one group of a conditional is kept: the first whose
+//! condition holds, or the `#else` one. The others are
+//! skipped without being expanded, evaluated or even checked
+//! for unknown directives.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static GROUPS: &[(&str, &str)] = &[
+    ("#ifdef A\na\n#endif", "a"),
+    ("#ifdef B\nb\n#endif", ""),
+    ("#ifndef B\nb\n#endif", "b"),
+    ("#ifndef A\na\n#else\nnot a\n#endif", "not a"),
+    ("#if 0\n0\n#elif 1\n1\n#elif 1\n2\n#else\n3\n#endif", "1"),
+    ("#if 0\n0\n#elif 0\n1\n#else\n2\n#endif", "2"),
+    ("#if B\n0\n#else\n1\n#endif", "1"),
+    ("#if 1\n#if 0\na\n#else\nb\n#endif\n#endif", "b"),
+    ("#if 0\n#if 1\na\n#else\nb\n#endif\nc\n#else\nd\n#endif", "d"),
+    ("#ifdef A\n#ifndef A\na\n#elif 1\nb\n#endif\n#endif", "b"),
+    ("#if 0\n#unknown\n#error not here\n#include \"missing.h\"\n#endif", ""),
+    ("#if 0\n#if 1 / 0\n#endif\n#elif 1 / 1\nok\n#endif", "ok"),
+    ("#if 1\nyes\n#elif 1 / 0\n#endif", "yes"),
+    ("#if 0\n'unterminated\n#endif", ""),
+];
+
+static C23_GROUPS: &[(&str, &str)] = &[
+    ("#if 0\n#elifdef A\na\n#endif", "a"),
+    ("#if 0\n#elifdef B\nb\n#elifndef B\nnot b\n#endif", "not b"),
+    ("#ifdef A\na\n#elifdef A\nagain\n#endif", "a"),
+];
+
+static ERRORS: &[(&str, &str)] = &[
+    ("#if 1\n", "error: unterminated conditional directive"),
+    ("#ifdef A\n#if 0\n#endif\n", "error: unterminated conditional directive"),
+    ("#endif\n", "error: conditional directive without #if"),
+    ("#else\n", "error: conditional directive without #if"),
+    ("#elif 1\n", "error: conditional directive without #if"),
+    ("#if 1\n#else\n#else\n#endif\n", "error: #else or #elif after #else"),
+    ("#if 1\n#else\n#elif 1\n#endif\n", "error: #else or #elif after #else"),
+    ("#if A\n#endif\n", "error: invalid preprocessor expression"),
+];
+
+fn preprocess(source: &str, standard: Standard) -> (String, Vec<String>) {
+    return common::preprocess(&format!("#define A\n{source}"), &Options::standard(standard));
+}
+
+#[test]
+fn groups() {
+    for (source, expected) in GROUPS {
+        assert_eq!(preprocess(source, Standard::C99), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn elifdef() {
+    for (source, expected) in C23_GROUPS {
+        assert_eq!(preprocess(source, Standard::C23), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn errors() {
+    for (source, expected) in ERRORS {
+        let (_, diagnostics) = preprocess(source, Standard::C99);
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
@@ -0,0 +1,49 @@
+//! # Has Embed
+//! C23's `__has_embed` tells whether `#embed` would find a
+//! resource, and whether it would be empty.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::fs;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static CASES: &[(&str, &str)] = &[
+    ("\"data.bin\"", "__STDC_EMBED_FOUND__"),
+    ("\"empty.bin\"", "__STDC_EMBED_EMPTY__"),
+    ("\"missing.bin\"", "__STDC_EMBED_NOT_FOUND__"),
+    ("<data.bin>", "__STDC_EMBED_NOT_FOUND__"),
+    ("\"data.bin\" limit(0)", "__STDC_EMBED_EMPTY__"),
+    ("\"data.bin\" __limit__(1)", "__STDC_EMBED_FOUND__"),
+    ("\"data.bin\" LIMIT", "__STDC_EMBED_EMPTY__"),
+    ("DATA", "__STDC_EMBED_FOUND__"),
+    ("\"data.bin\" prefix(1) suffix(,) if_empty(0)", "__STDC_EMBED_FOUND__"),
+    ("\"data.bin\" offset(1)", "__STDC_EMBED_NOT_FOUND__"),
+    ("\"data.bin\" vendor::param(1)", "__STDC_EMBED_NOT_FOUND__"),
+];
+
+#[test]
+fn has_embed() {
+    let dir = std::env::temp_dir().join(format!("tornado-has-embed-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("data.bin"), b"abc").unwrap();
+    fs::write(dir.join("empty.bin"), b"").unwrap();
+    let options = Options { path: dir.join("test.c"), standard: Standard::C23, ..Options::default() };
+    for (operand, expected) in CASES {
+        let prelude = "#define DATA \"data.bin\"\n#define LIMIT limit(0)\n";
+        let condition = format!("__has_embed({operand}) == {expected}");
+        assert_eq!(common::evaluate(prelude, &condition, &options), (Some(true), vec![]), "__has_embed({operand})");
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn embed_results() {
+    let source = "__STDC_EMBED_NOT_FOUND__ __STDC_EMBED_FOUND__ __STDC_EMBED_EMPTY__\n\
+        #ifdef __has_embed\ndefined\n#endif\n";
+    let expected = ("0 1 2\ndefined".to_string(), vec![]);
+    assert_eq!(common::preprocess(source, &Options::standard(Standard::C23)), expected);
+}
@@ -0,0 +1,44 @@
+//! # Has Feature
+//! `__has_attribute`, `__has_c_attribute`, `__has_builtin`,
+//! `__has_feature` and `__has_extension` only report what the
+//! compiler can handle, which is nothing yet, whatever the
+//! standard.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static QUERIES: &[&str] = &[
+    "__has_attribute(packed)", "__has_attribute(__packed__)", "__has_attribute(aligned)",
+    "__has_c_attribute(deprecated)", "__has_c_attribute(nodiscard)", "__has_c_attribute(gnu::packed)",
+    "__has_builtin(__builtin_expect)", "__has_builtin(__builtin_offsetof)",
+    "__has_feature(c_static_assert)", "__has_feature(c_alignas)", "__has_extension(c_generic_selections)",
+];
+
+/// Evaluates `condition` in an `#if`, checking that it is
+/// evaluated without diagnostics.
+fn evaluate(condition: &str, standard: Standard) -> bool {
+    match common::evaluate("", condition, &Options::standard(standard)) {
+        (Some(value), diagnostics) if diagnostics.is_empty() => return value,
+        other => panic!("{condition:?} gives {other:?}")
+    }
+}
+
+#[test]
+fn nothing_is_supported_yet() {
+    for query in QUERIES {
+        for standard in [Standard::C89, Standard::C11, Standard::C23] {
+            assert!(!evaluate(query, standard), "{query:?} in {standard:?}");
+        }
+    }
+}
+
+#[test]
+fn queries_are_defined() {
+    for query in ["__has_attribute", "__has_c_attribute", "__has_builtin", "__has_feature", "__has_extension"] {
+        assert!(evaluate(&format!("defined({query})"), Standard::C23), "{query:?}");
+    }
+}
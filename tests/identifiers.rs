@@ -0,0 +1,62 @@
+//! # Identifiers
+//! Identifiers can be written with Unicode characters and
+//! UCNs (C99 6.4.2.1), and are then just as good as ASCII
+//! ones to the preprocessor: they can be macro names and
+//! operands of `#ifdef` and `defined`.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static MACRO_NAMES: &[(&str, &str)] = &[
+    ("#define 日本 42\n日本", "42"),
+    ("#define café 1\ncafé", "1"),
+    ("#define \\u00C0x 1\n\\u00C0x", "1"),
+    ("#define \\u00C0x 1\nÀx", "1"),
+    ("#define F(名前) [名前]\nF(1)", "[1]"),
+    ("#define 日本 42\n#undef 日本\n日本", "日本"),
+    ("#define _\\U000065E5 1\n_日", "1"),
+];
+
+static DEFINED: &[(&str, bool)] = &[
+    ("defined 日本", true),
+    ("defined(\\u00C0x)", true),
+    ("defined Àx", true),
+    ("defined ünknown", false),
+];
+
+fn options() -> Options {
+    return Options::standard(Standard::C11);
+}
+
+#[test]
+fn macro_names() {
+    for (source, expected) in MACRO_NAMES {
+        assert_eq!(common::preprocess(source, &options()), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn defined() {
+    for (condition, expected) in DEFINED {
+        let prelude = "#define 日本\n#define \\u00C0x\n";
+        assert_eq!(common::evaluate(prelude, condition, &options()), (Some(*expected), vec![]), "{condition:?}");
+    }
+}
+
+#[test]
+fn ifdef() {
+    let source = "#define 日本\n#ifdef 日本\nyes\n#endif\n#ifndef \\u00C0x\nno\n#endif\n";
+    assert_eq!(common::preprocess(source, &options()), ("yes\nno".to_string(), vec![]));
+}
+
+#[test]
+fn not_identifiers() {
+    for source in ["#define 1x 1\n", "#define \"s\" 1\n", "#define + 1\n", "#define @ 1\n"] {
+        let (_, diagnostics) = common::preprocess(source, &options());
+        assert_eq!(diagnostics, ["error: macro names must be identifiers"], "{source:?}");
+    }
+}
@@ -0,0 +1,85 @@
+//! # Includes
+//! `#include "file"` is searched in the directory of the
+//! including file, then like `#include <file>`, which is
+//! searched in the `-I` and then the `-isystem` directories.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::{Options, TempDir};
+use tornado_cc::front::cpp::SearchPaths;
+
+static FILES: &[(&str, &str)] = &[
+    ("main/local.h", "local"),
+    ("main/both.h", "main"),
+    ("main/sub/nested.h", "#include \"sibling.h\"\n"),
+    ("main/sub/sibling.h", "sibling"),
+    ("main/file.h", "__FILE__ __LINE__"),
+    ("main/self.h", "#include \"self.h\"\n"),
+    ("user/both.h", "user"),
+    ("user/user.h", "from user"),
+    ("user/shadow.h", "user shadow"),
+    ("quote/quote.h", "from quote"),
+    ("system/shadow.h", "system shadow"),
+    ("system/system.h", "from system"),
+];
+
+static CASES: &[(&str, &str)] = &[
+    ("#include \"local.h\"", "local"),
+    ("#include \"both.h\"", "main"),
+    ("#include <both.h>", "user"),
+    ("#include \"user.h\"", "from user"),
+    ("#include \"quote.h\"", "from quote"),
+    ("#include <shadow.h>", "user shadow"),
+    ("#include <system.h>", "from system"),
+    ("#include \"sub/nested.h\"", "sibling"),
+    ("#define H \"local.h\"\n#include H", "local"),
+    ("#define H <user.h>\n#include H", "from user"),
+    ("#define STR(x) #x\n#define XSTR(x) STR(x)\n#include XSTR(local.h)", "local"),
+    ("a\n#include \"local.h\"\nb", "a\nlocal\nb"),
+];
+
+static ERRORS: &[(&str, &str)] = &[
+    ("#include \"missing.h\"", "error: 'missing.h' file not found"),
+    ("#include <local.h>", "error: 'local.h' file not found"),
+    ("#include <quote.h>", "error: 'quote.h' file not found"),
+    ("#include", "error: #include expects \"FILENAME\" or <FILENAME>"),
+    ("#include local.h", "error: #include expects \"FILENAME\" or <FILENAME>"),
+    ("#include \"self.h\"", "error: #include nested too deeply"),
+];
+
+fn options(dir: &TempDir) -> Options {
+    let search = SearchPaths {
+        quote: vec![dir.path.join("quote")],
+        angled: vec![dir.path.join("user")],
+        system: vec![dir.path.join("system")],
+    };
+    return Options { search, ..Options::path(&dir.path.join("main/test.c")) };
+}
+
+#[test]
+fn search_order() {
+    let dir = TempDir::new("includes-search", FILES);
+    for (source, expected) in CASES {
+        assert_eq!(common::preprocess(source, &options(&dir)), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn errors() {
+    let dir = TempDir::new("includes-errors", FILES);
+    for (source, expected) in ERRORS {
+        let (_, diagnostics) = common::preprocess(source, &options(&dir));
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
+
+#[test]
+fn file_and_line_of_the_header() {
+    let dir = TempDir::new("includes-file", FILES);
+    let (text, diagnostics) = common::preprocess("\n\n#include \"file.h\"\n__LINE__", &options(&dir));
+    let header = dir.path.join("main/file.h");
+    assert_eq!(text, format!("\"{}\" 1\n4", header.display()));
+    assert_eq!(diagnostics, Vec::<String>::new());
+}
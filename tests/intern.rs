@@ -0,0 +1,44 @@
+//! # Intern
+//! A `Symbol` stands for the same string in every thread,
+//! whichever thread interned it.
+
+#![allow(clippy::needless_return)]
+
+use std::thread;
+
+use tornado_cc::util::intern::Symbol;
+
+static NAMES: &[&str] = &["main", "printf", "__VA_ARGS__", "", "x"];
+
+#[test]
+fn symbols_across_threads() {
+    let here: Vec<Symbol> = NAMES.iter().map(|name| Symbol::intern(name)).collect();
+    let there: Vec<Symbol> = thread::spawn(|| {
+        let _ = Symbol::intern("only interned in the other thread");
+        return NAMES.iter().rev().map(|name| Symbol::intern(name)).collect();
+    }).join().unwrap();
+    for (symbol, name) in here.iter().zip(NAMES) {
+        assert_eq!(symbol.as_str(), *name);
+        assert!(there.contains(symbol), "{name:?}");
+        let moved = *symbol;
+        assert_eq!(thread::spawn(move || moved.as_str()).join().unwrap(), *name);
+    }
+}
+
+#[test]
+fn many_symbols_across_threads() {
+    // Enough for a few chunks of the interner
+    let names: Vec<String> = (0..10_000).map(|i| format!("name{i}")).collect();
+    let symbols: Vec<Symbol> = names.iter().map(|name| Symbol::intern(name)).collect();
+    let readers: Vec<_> = (0..4).map(|_| {
+        let symbols = symbols.clone();
+        return thread::spawn(move || symbols.iter().map(|symbol| symbol.as_str()).collect::<Vec<_>>());
+    }).collect();
+    let writer = thread::spawn(|| (0..10_000).map(|i| Symbol::intern(&format!("other{i}"))).collect::<Vec<_>>());
+    for reader in readers {
+        assert_eq!(reader.join().unwrap(), names);
+    }
+    for (i, symbol) in writer.join().unwrap().into_iter().enumerate() {
+        assert_eq!(symbol.as_str(), format!("other{i}"));
+    }
+}
@@ -0,0 +1,98 @@
+//! # Keywords
+//! Which words are keywords depends on the standard and
+//! the extensions the lexer is set to, anything else is
+//! lexed as an identifier.
+
+#![allow(clippy::needless_return)]
+
+use std::process::Command;
+
+use tornado_cc::front::lex::{Lexer, TokenType};
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::lang::{Extensions, Standard};
+use tornado_cc::util::scan::source::FileId;
+
+/// Lexes the single word `source`, returning its token type.
+fn lex(source: &str, standard: Standard, extensions: Extensions) -> TokenType {
+    let mut diag = Diagnostics::new();
+    let mut lexer = Lexer::new(source.as_bytes(), FileId::default(), &mut diag);
+    lexer.set_standard(standard);
+    lexer.set_extensions(extensions);
+    let tokens: Vec<TokenType> = lexer.map(|tok| tok.tokentype).collect();
+    assert!(diag.is_empty(), "{source:?} has diagnostics");
+    match tokens.as_slice() {
+        [tokentype, TokenType::EOF] => return tokentype.clone(),
+        _ => panic!("{source:?} is not a single token")
+    }
+}
+
+fn is_keyword(source: &str, standard: Standard, extensions: Extensions) -> bool {
+    return !matches!(lex(source, standard, extensions), TokenType::IDENTIFIER(_));
+}
+
+#[test]
+fn standard_keywords() {
+    use Standard::*;
+    let cases: &[(&str, Standard)] = &[
+        ("int", C89), ("sizeof", C89),
+        ("inline", C99), ("restrict", C99), ("_Bool", C99), ("_Pragma", C99),
+        ("_Atomic", C11), ("_Alignas", C11), ("_Alignof", C11), ("_Generic", C11),
+        ("_Noreturn", C11), ("_Static_assert", C11), ("_Thread_local", C11),
+        ("bool", C23), ("true", C23), ("false", C23), ("nullptr", C23),
+        ("constexpr", C23), ("typeof", C23), ("static_assert", C23), ("thread_local", C23),
+    ];
+    for (word, since) in cases {
+        for standard in [C89, C99, C11, C17, C23] {
+            let expected = standard >= *since;
+            assert_eq!(is_keyword(word, standard, Extensions::default()), expected, "{word:?} in {standard:?}");
+        }
+    }
+}
+
+#[test]
+fn extension_keywords() {
+    let gnu = Extensions { gnu: true, ..Default::default() };
+    let msvc = Extensions { msvc: true, ..Default::default() };
+    for word in ["__attribute__", "asm", "__typeof__", "__extension__", "__inline__"] {
+        assert!(is_keyword(word, Standard::C89, gnu), "{word:?}");
+        assert!(!is_keyword(word, Standard::C23, msvc), "{word:?}");
+    }
+    for word in ["__declspec", "__int64"] {
+        assert!(is_keyword(word, Standard::C89, msvc), "{word:?}");
+        assert!(!is_keyword(word, Standard::C23, gnu), "{word:?}");
+    }
+    for standard in [Standard::C89, Standard::C23] {
+        for extensions in [Extensions::default(), gnu, msvc] {
+            assert!(is_keyword("__asm__", standard, extensions), "__asm__ in {standard:?}");
+        }
+    }
+}
+
+#[test]
+fn alternative_spellings() {
+    let gnu = Extensions { gnu: true, ..Default::default() };
+    assert_eq!(lex("bool", Standard::C23, gnu), TokenType::BOOL);
+    assert_eq!(lex("__inline__", Standard::C89, gnu), TokenType::INLINE);
+    assert_eq!(lex("__typeof__", Standard::C89, gnu), TokenType::TYPEOF);
+    assert_eq!(lex("asm", Standard::C89, gnu), TokenType::ASM);
+    assert_eq!(lex("__asm__", Standard::C89, Extensions::default()), TokenType::ASM);
+}
+
+/// Runs the compiler on the single word `source`, returning
+/// what it prints about its tokens.
+fn compile(source: &str, args: &[&str]) -> String {
+    let path = std::env::temp_dir().join(format!("tornado-keywords-{}-{}.c", std::process::id(), args.join("")));
+    std::fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_tornado-cc")).args(args).arg(&path).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    return String::from_utf8_lossy(&output.stdout).into_owned();
+}
+
+#[test]
+fn default_dialect() {
+    // Without -std, like GCC, it is gnu17
+    assert_eq!(compile("__STDC_VERSION__\n", &["-E", "-P"]), "201710L\n");
+    assert!(compile("asm\n", &[]).contains("tokentype: ASM"));
+    assert!(compile("asm\n", &["-std=gnu17"]).contains("tokentype: ASM"));
+    assert!(compile("asm\n", &["-std=c17"]).contains("tokentype: IDENTIFIER("));
+}
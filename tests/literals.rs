@@ -0,0 +1,52 @@
+//! # Literals
+//! String literals and character constants are checked as
+//! they leave the preprocessor, but adjacent string literals
+//! are only concatenated past it (translation phase 6), so
+//! `-E` does not mind the ones that can't be.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static CONCATENATION: &[(&str, bool)] = &[
+    ("\"a\" \"b\"", true),
+    ("\"a\" L\"b\"", true),
+    ("L\"a\" \"b\" L\"c\"", true),
+    ("u8\"a\" \"b\"", true),
+    ("u\"a\" U\"b\"", false),
+    ("L\"a\" \"b\" u\"c\"", false),
+    ("u8\"a\" L\"b\"", false),
+];
+
+static MALFORMED: &[(&str, &str)] = &[
+    ("''", "error: empty character constant"),
+    ("'ab'", "warning: multi-character character constant"),
+];
+
+fn options(finish: bool) -> Options {
+    return Options { standard: Standard::C23, finish, ..Options::default() };
+}
+
+#[test]
+fn concatenation() {
+    for (source, compatible) in CONCATENATION {
+        assert_eq!(common::preprocess(source, &options(false)), (source.to_string(), vec![]), "{source:?}");
+        let (_, diagnostics) = common::preprocess(source, &options(true));
+        let expected: &[&str] = match compatible {
+            true => &[],
+            false => &["error: concatenation of string literals with different encoding prefixes"],
+        };
+        assert_eq!(diagnostics, expected, "{source:?}");
+    }
+}
+
+#[test]
+fn malformed() {
+    for (source, expected) in MALFORMED {
+        let (_, diagnostics) = common::preprocess(source, &options(false));
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
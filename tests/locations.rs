@@ -0,0 +1,64 @@
+//! # Locations
+//! Tokens only carry their file and byte offset, and the
+//! `SourceManager` turns them into lines and columns, both
+//! counted from 0, the columns in bytes.
+
+#![allow(clippy::needless_return)]
+
+use tornado_cc::front::lex::{Lexer, TokenType};
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::scan::location::{Location, Span};
+use tornado_cc::util::scan::source::SourceManager;
+
+// Each source has the tokens `a`, `b` and `c`, at those lines and columns
+static SOURCES: &[(&str, [(usize, usize); 3])] = &[
+    ("a b c", [(0, 0), (0, 2), (0, 4)]),
+    ("a\nb\nc", [(0, 0), (1, 0), (2, 0)]),
+    ("a\r\n b\r\n  c", [(0, 0), (1, 1), (2, 2)]),
+    ("\ta\t\tb\n\n\n\tc", [(0, 1), (0, 4), (3, 1)]),
+    ("/* é */ a \"ü\" b\n\u{FEFF} c", [(0, 9), (0, 16), (1, 4)]),
+    ("a \\\n b \\\n\\\n c", [(0, 0), (1, 1), (3, 1)]),
+    ("a /* one\ntwo */ b // three\n c", [(0, 0), (1, 7), (2, 1)]),
+    ("\n\n\n\n\n\n\n\n\n\na b\n\n\n\n\n\n\n\n\n\nc", [(10, 0), (10, 2), (20, 0)]),
+];
+
+#[test]
+fn lines_and_columns() {
+    for (source, expected) in SOURCES {
+        let mut sources = SourceManager::new();
+        let file = sources.add("test.c", source.as_bytes());
+        let mut diag = Diagnostics::new();
+        let tokens: Vec<_> = Lexer::new(sources.source(file), file, &mut diag)
+            .filter(|tok| matches!(tok.tokentype, TokenType::IDENTIFIER(_)))
+            .collect();
+        let found: Vec<_> = tokens.iter().map(|tok| (sources.line(tok.location), sources.column(tok.location))).collect();
+        assert_eq!(found, expected, "{source:?}");
+    }
+}
+
+#[test]
+fn spans() {
+    let mut sources = SourceManager::new();
+    let file = sources.add("test.c", b"ab\\\ncd  \"x\\\ny\"".as_slice());
+    let mut diag = Diagnostics::new();
+    let spans: Vec<_> = Lexer::new(sources.source(file), file, &mut diag).map(|tok| tok.span).collect();
+    assert_eq!(spans, [Span { file, start: 0, end: 6 }, Span { file, start: 8, end: 14 }, Span { file, start: 14, end: 14 }]);
+}
+
+#[test]
+fn files() {
+    let mut sources = SourceManager::new();
+    let main = sources.add("main.c", b"int x;\nint y;".as_slice());
+    let header = sources.add("header.h", b"\n\nint z;".as_slice());
+    assert_ne!(main, header);
+    assert_eq!(sources.path(header).to_str(), Some("header.h"));
+    assert_eq!(sources.source(main), b"int x;\nint y;");
+
+    let location = Location { file: main, offset: 11 };
+    assert_eq!((sources.line(location), sources.column(location)), (1, 4));
+    let location = Location { file: header, offset: 6 };
+    assert_eq!((sources.line(location), sources.column(location)), (2, 4));
+    // The end of the file is a location too
+    let location = Location { file: main, offset: 13 };
+    assert_eq!((sources.line(location), sources.column(location)), (1, 6));
+}
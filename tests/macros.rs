@@ -0,0 +1,110 @@
+//! # Macros
+//! Macros are expanded like C99 6.10.3 says: arguments are
+//! fully expanded unless they are operands of `#` or `##`, the
+//! result is rescanned with the macro painted blue.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static OBJECT_LIKE: &[(&str, &str)] = &[
+    ("#define A 1\nA", "1"),
+    ("#define A\nx A y", "x y"),
+    ("#define A B\n#define B 2\nA", "2"),
+    ("#define A (1 + 2)\nA * 3", "(1 + 2) * 3"),
+    ("#define A 1\n#undef A\nA", "A"),
+];
+
+// The neighbours of an empty expansion must not paste in the output
+static EMPTY: &[(&str, &str)] = &[
+    ("#define E\n+E+b", "+ +b"),
+    ("#define F()\n-F()-d", "- -d"),
+    ("#define E\ny/E/z", "y/ /z"),
+    ("#define E\n#define F()\nx E y F() z", "x y z"),
+];
+
+static FUNCTION_LIKE: &[(&str, &str)] = &[
+    ("#define F(x) [x]\nF(1)", "[1]"),
+    ("#define F(x, y) x + y\nF((1, 2), 3)", "(1, 2) + 3"),
+    ("#define F(x) [x]\nF ( 1 )", "[1]"),
+    ("#define F(x) [x]\nF", "F"),
+    ("#define F(x) [x]\nF\n(2)", "[2]"),
+    ("#define F() 0\nF()", "0"),
+    ("#define F(x) [x]\nF()", "[]"),
+    ("#define ONE 1\n#define F(x) [x]\nF(ONE)", "[1]"),
+];
+
+static PAINTED_BLUE: &[(&str, &str)] = &[
+    ("#define A A\nA", "A"),
+    ("#define A B\n#define B A\nA B", "A B"),
+    ("#define f(x) f(x)\nf(1)", "f(1)"),
+    ("#define f(x) x f\nf(1)(2)", "1 f(2)"),
+    ("#define foo foo a\nfoo", "foo a"),
+    ("#define NIL(x) x\n#define G NIL(G)\nG", "G"),
+    ("#define A B\n#define B A\n#define F(x) x\nF(A)", "A"),
+];
+
+static RESCAN: &[(&str, &str)] = &[
+    ("#define LPAREN (\n#define F(x) [x]\nF LPAREN 1)", "F ( 1)"),
+    ("#define F(x) [x]\n#define G F\nG(1)", "[1]"),
+    ("#define F(x) [x]\n#define APPLY(m, a) m(a)\nAPPLY(F, 2)", "[2]"),
+    ("#define EMPTY\n#define F(x) [x]\nF EMPTY (1)", "F (1)"),
+    ("#define f(a) a*g\n#define g(a) f(a)\nf(2)(9)", "2*9*g"),
+];
+
+fn preprocess(source: &str) -> (String, Vec<String>) {
+    return common::preprocess(source, &Options::standard(Standard::C23));
+}
+
+fn check(cases: &[(&str, &str)]) {
+    for (source, expected) in cases {
+        assert_eq!(preprocess(source), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn object_like() {
+    check(OBJECT_LIKE);
+}
+
+#[test]
+fn empty() {
+    check(EMPTY);
+}
+
+#[test]
+fn function_like() {
+    check(FUNCTION_LIKE);
+}
+
+#[test]
+fn wrong_number_of_arguments() {
+    for source in ["#define F(x, y) [x|y]\nF([1, 2], {3, 4})", "#define F(x) [x]\nF(1, 2)", "#define F(x, y) x\nF(1)"] {
+        let (_, diagnostics) = preprocess(source);
+        assert_eq!(diagnostics, ["error: wrong number of arguments passed to macro"], "{source:?}");
+    }
+}
+
+#[test]
+fn painted_blue() {
+    check(PAINTED_BLUE);
+}
+
+#[test]
+fn rescan() {
+    check(RESCAN);
+}
+
+#[test]
+fn example_6() {
+    let valid = "#define OBJ_LIKE (1-1)\n#define OBJ_LIKE /* white space */ (1-1) /* other */\n\
+        #define FUNC_LIKE(a) ( a )\n#define FUNC_LIKE( a )( /* note the white space */ \\\n a /* other stuff on this line\n */ )\n";
+    assert_eq!(preprocess(valid), (String::new(), vec![]));
+    for redefinition in ["#define OBJ_LIKE (0)", "#define OBJ_LIKE (1 - 1)", "#define FUNC_LIKE(b) ( a )", "#define FUNC_LIKE(b) ( b )"] {
+        let (_, diagnostics) = preprocess(&format!("{valid}{redefinition}\n"));
+        assert_eq!(diagnostics, ["warning: macro redefined"], "{redefinition:?}");
+    }
+}
@@ -0,0 +1,46 @@
+//! # Numbers
+//! `NumberParser` reads integer constants in every base, the
+//! prefix being skipped, on top of the `Scanner`.
+
+#![allow(clippy::needless_return)]
+
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::num::NumberParser;
+
+static INTEGERS: &[(&str, i128)] = &[
+    ("7", 7),
+    ("123", 123),
+    ("017", 0o17),
+    ("0x1F", 0x1F),
+    ("0Xff", 0xFF),
+    ("0b101", 0b101),
+    ("42 ", 42),
+    ("010\n", 8),
+    ("12u", 12),
+];
+
+static MALFORMED: &[&str] = &["12a", "019", "0x1G", "0b102"];
+
+/// Parses `source`, returning the integer and how many
+/// diagnostics there are.
+fn parse(source: &str) -> (i128, usize) {
+    let mut diag = Diagnostics::new();
+    let mut parser = NumberParser::new(source.as_bytes(), &mut diag);
+    parser.num();
+    let value = *parser.get_num().int().unwrap();
+    return (value, diag.len());
+}
+
+#[test]
+fn integers() {
+    for (source, expected) in INTEGERS {
+        assert_eq!(parse(source), (*expected, 0), "{source:?}");
+    }
+}
+
+#[test]
+fn malformed() {
+    for source in MALFORMED {
+        assert_eq!(parse(source).1, 1, "{source:?}");
+    }
+}
@@ -0,0 +1,51 @@
+//! # Once
+//! Headers with `#pragma once`, or wrapped in an include guard
+//! whose macro is defined, are only included once.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::{Options, TempDir};
+
+static FILES: &[(&str, &str)] = &[
+    ("once.h", "#pragma once\nonce\n"),
+    ("late_once.h", "late\n#pragma once\n"),
+    ("guard.h", "// comment\n#ifndef GUARD_H\n#define GUARD_H\nguard\n#endif\n"),
+    ("defined.h", "#if !defined(DEFINED_H)\n#define DEFINED_H\ndefined\n#endif\n"),
+    ("outside.h", "#ifndef OUTSIDE_H\n#define OUTSIDE_H\n#endif\noutside\n"),
+    ("else.h", "#ifndef ELSE_H\n#define ELSE_H\nfirst\n#else\nagain\n#endif\n"),
+    ("unguarded.h", "unguarded\n"),
+    ("sub/once.h", "#pragma once\nsub\n"),
+    ("link.h", "#include \"once.h\"\n"),
+];
+
+static CASES: &[(&str, &str)] = &[
+    ("#include \"once.h\"\n#include \"once.h\"", "once"),
+    ("#include \"once.h\"\n#include \"./once.h\"\n#include \"sub/../once.h\"", "once"),
+    ("#include \"link.h\"\n#include \"once.h\"", "once"),
+    ("#include \"late_once.h\"\n#include \"late_once.h\"", "late"),
+    ("#include \"once.h\"\n#include \"sub/once.h\"", "once\nsub"),
+    ("#include \"guard.h\"\n#include \"guard.h\"", "guard"),
+    ("#include \"guard.h\"\n#undef GUARD_H\n#include \"guard.h\"", "guard\nguard"),
+    ("#define GUARD_H\n#include \"guard.h\"", ""),
+    ("#include \"defined.h\"\n#include \"defined.h\"", "defined"),
+    ("#include \"outside.h\"\n#include \"outside.h\"", "outside\noutside"),
+    ("#include \"else.h\"\n#include \"else.h\"", "first\nagain"),
+    ("#include \"unguarded.h\"\n#include \"unguarded.h\"", "unguarded\nunguarded"),
+];
+
+#[test]
+fn included_once() {
+    let dir = TempDir::new("once", FILES);
+    let options = Options::path(&dir.path.join("test.c"));
+    for (source, expected) in CASES {
+        assert_eq!(common::preprocess(source, &options), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn once_in_the_main_file() {
+    // It does nothing there, but it is not an unknown pragma either
+    assert_eq!(common::preprocess("#pragma once\nmain", &Options::default()), ("main".to_string(), vec![]));
+}
@@ -0,0 +1,147 @@
+//! # Operators
+//! `#` turns an argument into a string literal, spelled as
+//! written, and `##` pastes two tokens into one, before the
+//! arguments around them are expanded (C99 6.10.3.2-3).
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static STRINGIZE: &[(&str, &str)] = &[
+    ("#define S(x) #x\nS(  a   b  )", "\"a b\""),
+    ("#define S(x) #x\nS(\"a\\n\" 'b')", "\"\\\"a\\\\n\\\" 'b'\""),
+    ("#define S(x) #x\nS('\"' \"'\")", "\"'\\\"' \\\"'\\\"\""),
+    ("#define S(x) #x\nS(a\\b)", "\"a\\b\""),
+    ("#define S(x) #x\n#define X 1\nS(X)", "\"X\""),
+    ("#define S(x) #x\n#define XS(x) S(x)\n#define X 1\nXS(X)", "\"1\""),
+    ("#define S(x) #x\nS(@ $)", "\"@ $\""),
+    ("#define S(x) #x\nS(  )", "\"\""),
+];
+
+static PASTE: &[(&str, &str)] = &[
+    ("#define P(a, b) a ## b\nP(x, y)", "xy"),
+    ("#define P(a, b) a ## b\nP(1, 2)", "12"),
+    ("#define P(a, b) a ## b\nP(+, =)", "+="),
+    ("#define P(a, b) a ## b\nP(<, <=)", "<<="),
+    ("#define P(a, b) a ## b\nP(, y) P(x, ) P(, )", "y x"),
+    ("#define P(a, b) a ## b\n#define xy 3\nP(x, y)", "3"),
+    ("#define P(a, b) a ## b\n#define A 1\nP(A, B)", "AB"),
+    ("#define P(a) a ## 1 ## 2\nP(x)", "x12"),
+    ("#define hash_hash # ## #\nhash_hash", "##"),
+    ("#define P(a, b) a ## b\nP(L, 'a') P(u8, \"s\")", "L'a' u8\"s\""),
+];
+
+static MISPLACED: &[(&str, &str)] = &[
+    ("#define S(x) #y\n", "error: '#' is not followed by a macro parameter"),
+    ("#define S(x) #\n", "error: '#' is not followed by a macro parameter"),
+    ("#define P(x) ## x\n", "error: '##' cannot appear at either end of a macro expansion"),
+    ("#define P(x) x ##\n", "error: '##' cannot appear at either end of a macro expansion"),
+    ("#define P ##\n", "error: '##' cannot appear at either end of a macro expansion"),
+];
+
+// The examples of C99 6.10.3.5 using # and ##
+static EXAMPLE_3: &str = "#define x 3
+#define f(a) f(x * (a))
+#undef x
+#define x 2
+#define g f
+#define z z[0]
+#define h g(~
+#define m(a) a(w)
+#define w 0,1
+#define t(a) a
+#define p() int
+#define q(x) x
+#define r(x,y) x ## y
+#define str(x) # x
+f(y+1) + f(f(z)) % t(t(g)(0) + t)(1);
+g(x+(3,4)-w) | h 5) & m
+(f)^m(m);
+p() i[q()] = { q(1), r(2,3), r(4,), r(,5), r(,) };
+char c[2][6] = { str(hello), str() };
+";
+
+static EXAMPLE_4: &str = "#define str(s) # s
+#define xstr(s) str(s)
+#define debug(s, t) printf(\"x\" # s \"= %d, x\" # t \"= %s\", \\
+ x ## s, x ## t)
+#define INCFILE(n) vers ## n
+#define glue(a, b) a ## b
+#define xglue(a, b) glue(a, b)
+#define HIGHLOW \"hello\"
+#define LOW LOW \", world\"
+debug(1, 2);
+fputs(str(strncmp(\"abc\\0d\", \"abc\", '\\4') // this goes away
+ == 0) str(: @\\n), s);
+xstr(INCFILE(2).h)
+glue(HIGH, LOW);
+xglue(HIGH, LOW)
+";
+
+static EXAMPLE_5: &str = "#define t(x,y,z) x ## y ## z
+int j[] = { t(1,2,3), t(,4,5), t(6,,7), t(8,9,),
+ t(10,,), t(,11,), t(,,12), t(,,) };
+";
+
+fn preprocess(source: &str) -> (String, Vec<String>) {
+    return common::preprocess(source, &Options::standard(Standard::C23));
+}
+
+fn check(cases: &[(&str, &str)]) {
+    for (source, expected) in cases {
+        assert_eq!(preprocess(source), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn stringize() {
+    check(STRINGIZE);
+}
+
+#[test]
+fn paste() {
+    check(PASTE);
+}
+
+#[test]
+fn invalid_paste() {
+    let (text, diagnostics) = preprocess("#define P(a, b) a ## b\nP(.., .)");
+    assert_eq!(text, ".. .");
+    assert_eq!(diagnostics, ["error: pasting does not give a valid preprocessing token"]);
+}
+
+#[test]
+fn misplaced() {
+    for (source, expected) in MISPLACED {
+        let (_, diagnostics) = preprocess(source);
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
+
+#[test]
+fn example_3() {
+    let expected = "f(2 * (y+1)) + f(2 * (f(2 * (z[0])))) % f(2 * (0)) + t(1);\n\
+        f(2 * (2+(3,4)-0,1)) | f(2 * (~ 5)) & f(2 * (0,1))^m(0,1);\n\
+        int i[] = { 1, 23, 4, 5, };\n\
+        char c[2][6] = { \"hello\", \"\" };";
+    assert_eq!(preprocess(EXAMPLE_3), (expected.to_string(), vec![]));
+}
+
+#[test]
+fn example_4() {
+    let expected = "printf(\"x\" \"1\" \"= %d, x\" \"2\" \"= %s\", x1, x2);\n\
+        fputs(\"strncmp(\\\"abc\\\\0d\\\", \\\"abc\\\", '\\\\4') == 0\" \": @\\n\", s);\n\
+        \"vers2.h\"\n\
+        \"hello\";\n\
+        \"hello\" \", world\"";
+    assert_eq!(preprocess(EXAMPLE_4), (expected.to_string(), vec![]));
+}
+
+#[test]
+fn example_5() {
+    let expected = "int j[] = { 123, 45, 67, 89,\n10, 11, 12, };";
+    assert_eq!(preprocess(EXAMPLE_5), (expected.to_string(), vec![]));
+}
@@ -0,0 +1,85 @@
+//! # Output
+//! `-E` prints the preprocessed tokens back as C source that
+//! keeps the lines of the original files, with linemarkers
+//! telling where they come from. `-P` drops the linemarkers
+//! and the blank lines.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::{Options, TempDir};
+
+static LINES: &[(&str, &str)] = &[
+    ("a\nb", "# 1 \"test.c\"\na\nb"),
+    ("a\n\n\nb", "# 1 \"test.c\"\na\n\n\nb"),
+    ("a\n#define X\n// comment\nb", "# 1 \"test.c\"\na\n\n\nb"),
+    ("a\n\n\n\n\n\n\n\n\nb", "# 1 \"test.c\"\na\n\n\n\n\n\n\n\n\nb"),
+    ("a\n\n\n\n\n\n\n\n\n\nb", "# 1 \"test.c\"\na\n# 11 \"test.c\"\nb"),
+    ("a \\\nb\nc", "# 1 \"test.c\"\na b\n\nc"),
+    ("a /* one\ntwo */ b\nc", "# 1 \"test.c\"\na b\n\nc"),
+    ("#define F(x) x\nF(1\n)\nc", "# 1 \"test.c\"\n\n1\n\nc"),
+    ("a\n#line 100\nb\n__LINE__", "# 1 \"test.c\"\na\n# 100 \"test.c\"\nb\n101"),
+    ("#line 7 \"other.c\"\n__FILE__", "# 1 \"test.c\"\n# 7 \"other.c\"\n\"other.c\""),
+    ("#pragma pack(1)\nint x;", "# 1 \"test.c\"\n#pragma pack(1)\nint x;"),
+    ("a _Pragma(\"pack(2)\") b", "# 1 \"test.c\"\na\n# 1 \"test.c\"\n#pragma pack(2)\n# 1 \"test.c\"\nb"),
+];
+
+static WITHOUT_LINEMARKERS: &[(&str, &str)] = &[
+    ("a\n\n\nb", "a\nb"),
+    ("a\n\n\n\n\n\n\n\n\n\nb", "a\nb"),
+    ("a\n#line 100\nb", "a\nb"),
+    ("a /* comment */ b", "a b"),
+    ("a/**/b", "a b"),
+];
+
+// Tokens that were not next to each other must not paste
+static SPACING: &[(&str, &str)] = &[
+    ("#define P +\nP+ +P", "+ + + +"),
+    ("#define M -\n-M M-", "- - - -"),
+    ("#define D .\nD.. ..D D.D", ".. . .. . .. ."),
+    ("#define I x\nI y Iy", "x y Iy"),
+    ("#define F() x\nF()F()", "x x"),
+    ("#define N 1\nN.e", "1 .e"),
+    ("#define S /\nS/ S*", "/ / / *"),
+    ("a+b (c)", "a+b (c)"),
+];
+
+fn linemarkers() -> Options {
+    return Options { linemarkers: true, ..Options::default() };
+}
+
+fn check(cases: &[(&str, &str)], options: &Options) {
+    for (source, expected) in cases {
+        assert_eq!(common::preprocess(source, options), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn lines() {
+    check(LINES, &linemarkers());
+}
+
+#[test]
+fn without_linemarkers() {
+    check(WITHOUT_LINEMARKERS, &Options::default());
+}
+
+#[test]
+fn spacing() {
+    check(SPACING, &Options::default());
+}
+
+#[test]
+fn included_files() {
+    let dir = TempDir::new("output", &[("h.h", "h1\nh2\n"), ("g.h", "#include \"h.h\"\ng\n")]);
+    let options = Options { linemarkers: true, ..Options::path(&dir.path.join("test.c")) };
+    let (main, g, h) = (dir.path.join("test.c"), dir.path.join("g.h"), dir.path.join("h.h"));
+    let (main, g, h) = (main.display(), g.display(), h.display());
+    let expected = format!("# 1 \"{main}\"\na\n\
+        # 1 \"{g}\" 1\n\
+        # 1 \"{h}\" 1\nh1\nh2\n\
+        # 2 \"{g}\" 2\ng\n\
+        # 3 \"{main}\" 2\nb");
+    assert_eq!(common::preprocess("a\n#include \"g.h\"\nb", &options), (expected, vec![]));
+}
@@ -0,0 +1,112 @@
+//! # Pragmas
+//! `#pragma` and `_Pragma` are handled by the handler of
+//! their namespace, and the rest are passed on or ignored.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::front::cpp::{Pragma, Preprocessor};
+use tornado_cc::util::diag::Status;
+
+static PASSED_ON: &[(&str, &str)] = &[
+    ("#pragma pack(1)\nint x;", "#pragma pack(1)\nint x;"),
+    ("#pragma pack(push, 4)", "#pragma pack(push, 4)"),
+    ("#pragma weak f = g", "#pragma weak f = g"),
+    ("#pragma STDC FP_CONTRACT OFF", "#pragma STDC FP_CONTRACT OFF"),
+    ("_Pragma(\"pack(2)\")", "#pragma pack(2)"),
+    ("_Pragma(L\"pack(2)\")", "#pragma pack(2)"),
+    ("_Pragma(\"tool \\\"a\\\\\\\\b\\\"\")", "#pragma tool \"a\\\\b\""),
+    ("#define P(x) _Pragma(#x)\nP(pack(8))", "#pragma pack(8)"),
+    ("#define P _Pragma(\"pack()\") x\nP", "#pragma pack()\nx"),
+    ("#pragma\nx", "x"),
+    ("#pragma unknown stuff\nx", "#pragma unknown stuff\nx"),
+];
+
+static MALFORMED: &[(&str, &str)] = &[
+    ("_Pragma", "error: _Pragma takes a parenthesized string literal"),
+    ("_Pragma(pack(1))", "error: _Pragma takes a parenthesized string literal"),
+    ("_Pragma(\"pack(1)\"", "error: _Pragma takes a parenthesized string literal"),
+    ("#pragma pack 4", "warning: malformed pragma ignored"),
+    ("#pragma weak", "warning: malformed pragma ignored"),
+    ("#pragma STDC FENV_ACCESS MAYBE", "warning: malformed pragma ignored"),
+    ("#pragma GCC diagnostic sideways \"-Wpragmas\"", "warning: malformed pragma ignored"),
+];
+
+static MESSAGES: &[(&str, &str)] = &[
+    ("#pragma GCC error \"not here\"\n", "error: not here"),
+    ("#pragma GCC warning \"deprecated\"\n", "warning: deprecated"),
+    ("_Pragma(\"GCC error \\\"not here\\\"\")\n", "error: not here"),
+    ("#define E(m) _Pragma(#m)\nE(GCC error \"expanded\")\n", "error: expanded"),
+    ("#pragma GCC error\n", "warning: malformed pragma ignored"),
+    ("#pragma GCC error not_a_string\n", "warning: malformed pragma ignored"),
+];
+
+fn passed_on(cpp: &mut Preprocessor, pragma: Pragma) -> Status {
+    cpp.pass_pragma(pragma);
+    return None;
+}
+
+fn ignored(_: &mut Preprocessor, _: Pragma) -> Status {
+    return None;
+}
+
+#[test]
+fn passed_on_pragmas() {
+    for (source, expected) in PASSED_ON {
+        assert_eq!(common::preprocess(source, &Options::default()), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn malformed() {
+    for (source, expected) in MALFORMED {
+        let (_, diagnostics) = common::preprocess(source, &Options::default());
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
+
+#[test]
+fn registered_handlers() {
+    let source = "#pragma mine 1\n_Pragma(\"mine 2\")\n#pragma pack(1)\n";
+    let (text, diagnostics) = common::preprocess_with(source, &Options::default(), |cpp| {
+        cpp.register_pragma("mine", passed_on);
+        cpp.register_pragma("pack", ignored);
+    });
+    assert_eq!((text.as_str(), diagnostics), ("#pragma mine 1\n#pragma mine 2", vec![]));
+}
+
+#[test]
+fn unknown_pragmas() {
+    let source = "#pragma unknown\n";
+    assert_eq!(common::preprocess(source, &Options::default()).1, Vec::<String>::new());
+    let (_, diagnostics) = common::preprocess_with(source, &Options::default(), |cpp| cpp.set_warning("unknown-pragmas", true));
+    assert_eq!(diagnostics, ["warning: unknown pragma ignored"]);
+}
+
+#[test]
+fn gcc_diagnostic() {
+    let redefine = "#define X 1\n#define X 2\n";
+    let ignored = format!("#pragma GCC diagnostic ignored \"-Wmacro-redefined\"\n{redefine}");
+    assert_eq!(common::preprocess(&ignored, &Options::default()).1, Vec::<String>::new());
+    let popped = format!("#pragma GCC diagnostic push\n{ignored}#pragma GCC diagnostic pop\n#define Y 1\n#define Y 2\n");
+    assert_eq!(common::preprocess(&popped, &Options::default()).1, ["warning: macro redefined"]);
+    let (_, diagnostics) = common::preprocess_with(redefine, &Options::default(), |cpp| cpp.set_warning("macro-redefined", false));
+    assert_eq!(diagnostics, Vec::<String>::new());
+}
+
+#[test]
+fn gcc_messages() {
+    for (source, expected) in MESSAGES {
+        let (_, diagnostics) = common::preprocess(source, &Options::default());
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
+
+#[test]
+fn gcc_error_is_not_ignored() {
+    let source = "#pragma GCC diagnostic ignored \"-Wpragmas\"\n#pragma GCC error \"still\"\n";
+    let (_, diagnostics) = common::preprocess(source, &Options::default());
+    assert_eq!(diagnostics, ["error: still"]);
+}
@@ -0,0 +1,89 @@
+//! # Prefixes
+//! String literals and character constants can be prefixed
+//! with `L`, `u`, `U` or `u8`, which picks the type of their
+//! characters and how they are encoded in it.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::front::lex::{Atom, Lexer, TokenType};
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::escape::{self, Encoding};
+use tornado_cc::util::lang::Standard;
+use tornado_cc::util::scan::source::FileId;
+
+static LITERALS: &[(&str, Encoding)] = &[
+    ("\"s\"", Encoding::Char),
+    ("L\"s\"", Encoding::Wide),
+    ("u\"s\"", Encoding::Utf16),
+    ("U\"s\"", Encoding::Utf32),
+    ("u8\"s\"", Encoding::Utf8),
+    ("'c'", Encoding::Char),
+    ("L'c'", Encoding::Wide),
+    ("u'c'", Encoding::Utf16),
+    ("U'c'", Encoding::Utf32),
+    ("u8'c'", Encoding::Utf8),
+];
+
+// They are only prefixes right before the quote
+static NOT_PREFIXES: &[&str] = &["L", "u8", "u", "U", "Lx", "u16", "U8", "l\"s\"", "LR\"s\"", "u8 \"s\""];
+
+// The units of "é😀" in each encoding
+static UNITS: &[(Encoding, &[u32])] = &[
+    (Encoding::Char, &[0xC3, 0xA9, 0xF0, 0x9F, 0x98, 0x80]),
+    (Encoding::Utf8, &[0xC3, 0xA9, 0xF0, 0x9F, 0x98, 0x80]),
+    (Encoding::Utf16, &[0xE9, 0xD83D, 0xDE00]),
+    (Encoding::Utf32, &[0xE9, 0x1F600]),
+];
+
+static VALUES: &[(&str, bool)] = &[
+    ("'\\xFF' == -1", true),
+    ("u8'\\xFF' == 255", true),
+    ("u'\\xFFFF' == 65535", true),
+    ("U'\\xFFFFFFFF' == 4294967295", true),
+    ("u'é' == 0xE9 && U'😀' == 0x1F600", true),
+    ("L'a' == 97 && u8'a' == 97", true),
+];
+
+fn lex(source: &str) -> Vec<TokenType> {
+    let mut diag = Diagnostics::new();
+    let mut lexer = Lexer::new(source.as_bytes(), FileId::default(), &mut diag);
+    lexer.set_standard(Standard::C23);
+    return lexer.map(|tok| tok.tokentype).collect();
+}
+
+#[test]
+fn literals() {
+    for (source, expected) in LITERALS {
+        let encoding = match lex(source).as_slice() {
+            [TokenType::ATOM(Atom::STRING(encoding, _) | Atom::CHAR(encoding, _)), TokenType::EOF] => *encoding,
+            tokens => panic!("{source:?} gives {tokens:?}")
+        };
+        assert_eq!(encoding, *expected, "{source:?}");
+    }
+}
+
+#[test]
+fn not_prefixes() {
+    for source in NOT_PREFIXES {
+        assert!(matches!(lex(source).first(), Some(TokenType::IDENTIFIER(_))), "{source:?}");
+    }
+}
+
+#[test]
+fn units() {
+    for (encoding, expected) in UNITS {
+        assert_eq!(escape::unescape("é\\U0001F600", *encoding, &mut vec![]), *expected, "{encoding:?}");
+    }
+    assert_eq!(escape::to_bytes(&[0x1F600], Encoding::Utf32), 0x1F600u32.to_ne_bytes());
+    assert_eq!(escape::to_bytes(&[0xD83D, 0xDE00], Encoding::Utf16), [0xD83Du16.to_ne_bytes(), 0xDE00u16.to_ne_bytes()].concat());
+}
+
+#[test]
+fn values() {
+    for (condition, expected) in VALUES {
+        assert_eq!(common::evaluate("", condition, &Options::standard(Standard::C23)), (Some(*expected), vec![]), "{condition:?}");
+    }
+}
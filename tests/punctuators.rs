@@ -0,0 +1,146 @@
+//! # Punctuators
+//! Every punctuator of C23 (6.4.6) must be lexed as the
+//! `TokenType` it stands for, and spelled back the way
+//! it is written. Digraphs are spelled as the punctuator
+//! they stand for. `::` is only a punctuator since C23.
+
+#![allow(clippy::needless_return)]
+
+use std::slice;
+
+use tornado_cc::front::lex::{Lexer, TokenType};
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::lang::Standard;
+use tornado_cc::util::scan::source::FileId;
+
+static PUNCTUATORS: &[(&str, TokenType)] = &[
+    ("[", TokenType::LEFT_BRACKET), ("]", TokenType::RIGHT_BRACKET),
+    ("(", TokenType::LEFT_PAREN), (")", TokenType::RIGHT_PAREN),
+    ("{", TokenType::LEFT_BRACE), ("}", TokenType::RIGHT_BRACE),
+    (".", TokenType::DOT), ("->", TokenType::ARROW),
+    ("++", TokenType::PLUSPLUS), ("--", TokenType::MINUSMINUS),
+    ("&", TokenType::AMPERSAND), ("*", TokenType::ASTERISK),
+    ("+", TokenType::PLUS), ("-", TokenType::MINUS),
+    ("~", TokenType::TILDE), ("!", TokenType::BANG),
+    ("/", TokenType::SLASH), ("%", TokenType::PERCENT),
+    ("<<", TokenType::DOUBLELESS), (">>", TokenType::DOUBLEGREATER),
+    ("<", TokenType::LESS), (">", TokenType::GREATER),
+    ("<=", TokenType::LESSEQ), (">=", TokenType::GREATEREQ),
+    ("==", TokenType::DOUBLEEQ), ("!=", TokenType::NEQ),
+    ("^", TokenType::CARET), ("|", TokenType::BAR),
+    ("&&", TokenType::DOUBLEAMPERSAND), ("||", TokenType::DOUBLEBAR),
+    ("?", TokenType::QUESTION), (":", TokenType::COLON),
+    ("::", TokenType::DOUBLECOLON), (";", TokenType::SEMICOLON),
+    ("...", TokenType::TRIPLET), ("=", TokenType::EQ),
+    ("*=", TokenType::ASTERISKEQ), ("/=", TokenType::SLASHEQ),
+    ("%=", TokenType::PERCENTEQ), ("+=", TokenType::PLUSEQ),
+    ("-=", TokenType::MINUSEQ), ("<<=", TokenType::DOUBLELESSEQ),
+    (">>=", TokenType::DOUBLEGREATEREQ), ("&=", TokenType::AMPERSANDEQ),
+    ("^=", TokenType::CARETEQ), ("|=", TokenType::BAREQ),
+    (",", TokenType::COMMA), ("#", TokenType::HASH),
+    ("##", TokenType::HASHTWICE),
+];
+
+static DIGRAPHS: &[(&str, TokenType)] = &[
+    ("<:", TokenType::LEFT_BRACKET), (":>", TokenType::RIGHT_BRACKET),
+    ("<%", TokenType::LEFT_BRACE), ("%>", TokenType::RIGHT_BRACE),
+    ("%:", TokenType::HASH), ("%:%:", TokenType::HASHTWICE),
+];
+
+/// Lexes `source` in `standard`, returning the token types
+/// without the EOF.
+fn lex_in(source: &str, standard: Standard) -> Vec<TokenType> {
+    let mut diag = Diagnostics::new();
+    let mut lexer = Lexer::new(source.as_bytes(), FileId::default(), &mut diag);
+    lexer.set_standard(standard);
+    let mut tokens: Vec<TokenType> = lexer.map(|tok| tok.tokentype).collect();
+    assert_eq!(tokens.pop(), Some(TokenType::EOF), "{source:?} does not end with EOF");
+    assert!(diag.is_empty(), "{source:?} has diagnostics");
+    return tokens;
+}
+
+fn lex(source: &str) -> Vec<TokenType> {
+    return lex_in(source, Standard::C23);
+}
+
+#[test]
+fn punctuators_round_trip() {
+    for (spelling, tokentype) in PUNCTUATORS {
+        assert_eq!(lex(spelling), slice::from_ref(tokentype), "{spelling:?}");
+        assert_eq!(tokentype.spelling(), Some(*spelling), "{tokentype:?}");
+    }
+}
+
+#[test]
+fn digraphs() {
+    for (spelling, tokentype) in DIGRAPHS {
+        assert_eq!(lex(spelling), slice::from_ref(tokentype), "{spelling:?}");
+    }
+}
+
+#[test]
+fn punctuators_separated_by_whitespace() {
+    let source = PUNCTUATORS.iter().map(|(spelling, _)| *spelling).collect::<Vec<_>>().join(" ");
+    let expected: Vec<TokenType> = PUNCTUATORS.iter().map(|(_, tokentype)| tokentype.clone()).collect();
+    assert_eq!(lex(&source), expected);
+}
+
+#[test]
+fn punctuator_pairs() {
+    // Each pair must be lexed as the longest punctuator first,
+    // which is the same as lexing the spelling of the pair.
+    // Pairs making a comment, a shebang or a digraph are not
+    // spelled back the same.
+    let skipped = ["//", "/*", "#!", "<:", ":>", "<%", "%>", "%:"];
+    for (a, _) in PUNCTUATORS {
+        for (b, _) in PUNCTUATORS {
+            let joined = format!("{a}{b}");
+            if skipped.iter().any(|s| joined.contains(s)) {
+                continue;
+            }
+            let spelled: String = lex(&joined).iter().map(|tok| tok.spelling().unwrap()).collect();
+            assert_eq!(spelled, joined, "{joined:?}");
+        }
+    }
+}
+
+#[test]
+fn maximal_munch() {
+    use TokenType::*;
+    let cases: &[(&str, &[TokenType])] = &[
+        ("+++++", &[PLUSPLUS, PLUSPLUS, PLUS]),
+        ("--->", &[MINUSMINUS, ARROW]),
+        ("<<<=", &[DOUBLELESS, LESSEQ]),
+        (">>>=", &[DOUBLEGREATER, GREATEREQ]),
+        ("....", &[TRIPLET, DOT]),
+        ("..", &[DOT, DOT]),
+        ("&&&", &[DOUBLEAMPERSAND, AMPERSAND]),
+        ("|||=", &[DOUBLEBAR, BAREQ]),
+        ("===", &[DOUBLEEQ, EQ]),
+        ("!==", &[NEQ, EQ]),
+        (":::", &[DOUBLECOLON, COLON]),
+        ("###", &[HASHTWICE, HASH]),
+        ("%:%:%:", &[HASHTWICE, HASH]),
+        ("<::>", &[LEFT_BRACKET, RIGHT_BRACKET]),
+        ("%%=", &[PERCENT, PERCENTEQ]),
+        ("^^=", &[CARET, CARETEQ]),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(lex(source), *expected, "{source:?}");
+    }
+}
+
+#[test]
+fn double_colon_before_c23() {
+    use TokenType::*;
+    let cases: &[(&str, &[TokenType])] = &[
+        ("::", &[COLON, COLON]),
+        (":::", &[COLON, COLON, COLON]),
+        (":>", &[RIGHT_BRACKET]),
+    ];
+    for standard in [Standard::C89, Standard::C99, Standard::C11, Standard::C17] {
+        for (source, expected) in cases {
+            assert_eq!(lex_in(source, standard), *expected, "{source:?} in {standard:?}");
+        }
+    }
+}
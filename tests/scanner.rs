@@ -0,0 +1,48 @@
+//! # Scanner
+//! `Scanner::next()` moves to the next item and returns it,
+//! and from then on it is the current item: `peek(0)` and
+//! `peek_back(0)`, at index `ptr` and offset `location`.
+
+#![allow(clippy::needless_return)]
+
+use tornado_cc::util::scan::Scanner;
+
+static ITEMS: &[u8] = b"My tiny";
+
+#[test]
+fn starts_at_the_first_item() {
+    let scan: Scanner<u8, 3> = Scanner::new(ITEMS);
+    assert_eq!(scan.ptr, 0);
+    assert_eq!(scan.location.offset, 0);
+    assert_eq!([scan.peek(0), scan.peek(1), scan.peek(2), scan.peek(3)], [Some(&b'M'), Some(&b'y'), Some(&b' '), None]);
+    assert_eq!([scan.peek_back(0), scan.peek_back(1), scan.peek_back(2)], [Some(&b'M'), None, None]);
+}
+
+#[test]
+fn next_returns_the_new_current_item() {
+    let mut scan: Scanner<u8, 3> = Scanner::new(ITEMS);
+    for (i, item) in ITEMS.iter().enumerate().skip(1) {
+        assert_eq!(scan.next(), Some(item), "item {i}");
+        assert_eq!(scan.ptr, i);
+        assert_eq!(scan.location.offset, i);
+        assert_eq!(scan.peek(0), Some(item));
+        assert_eq!(scan.peek(1), ITEMS.get(i + 1));
+        assert_eq!(scan.peek(2), ITEMS.get(i + 2));
+        assert_eq!(scan.peek_back(0), Some(item));
+        assert_eq!(scan.peek_back(1), ITEMS.get(i - 1));
+        assert_eq!(scan.peek_back(2), i.checked_sub(2).and_then(|i| ITEMS.get(i)));
+    }
+    assert_eq!(scan.next(), None);
+    assert_eq!(scan.peek(0), None);
+    assert_eq!(scan.peek_back(1), ITEMS.last());
+}
+
+#[test]
+fn nth_skips_items() {
+    let mut scan: Scanner<u8, 2> = Scanner::new(ITEMS);
+    assert_eq!(scan.nth(2), Some(&b't'));
+    assert_eq!(scan.peek(0), Some(&b't'));
+    assert_eq!(scan.peek(1), Some(&b'i'));
+    assert_eq!(scan.nth(3), None);
+    assert_eq!(scan.peek(0), None);
+}
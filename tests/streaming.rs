@@ -0,0 +1,67 @@
+//! # Streaming
+//! The lexer is an iterator: it lexes a token only when asked
+//! for one, ends with a single `EOF` token and then stays
+//! done.
+
+#![allow(clippy::needless_return)]
+
+use tornado_cc::front::lex::{Lexer, TokenType};
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::scan::source::FileId;
+
+static SOURCES: &[(&str, usize)] = &[
+    ("", 0),
+    ("  \n\t// nothing\n/* at all */", 0),
+    ("int x;", 3),
+    ("a\\\nb", 1),
+    ("#define X 1\nX", 5),
+    ("'unterminated", 1),
+];
+
+#[test]
+fn ends_with_eof() {
+    for (source, count) in SOURCES {
+        let mut diag = Diagnostics::new();
+        let mut lexer = Lexer::new(source.as_bytes(), FileId::default(), &mut diag);
+        let tokens: Vec<_> = lexer.by_ref().collect();
+        assert_eq!(tokens.len(), count + 1, "{source:?}");
+        assert_eq!(tokens.last().unwrap().tokentype, TokenType::EOF, "{source:?}");
+        assert!(lexer.next().is_none() && lexer.next().is_none(), "{source:?}");
+    }
+}
+
+#[test]
+fn lazy() {
+    // The invalid UTF-8 is only reported once it is lexed
+    let source = b"a b \xFF c";
+    let mut diag = Diagnostics::new();
+    let mut lexer = Lexer::new(source, FileId::default(), &mut diag);
+    let first: Vec<_> = lexer.by_ref().take(2).map(|tok| tok.tokentype).collect();
+    assert_eq!(first, [TokenType::IDENTIFIER("a".into()), TokenType::IDENTIFIER("b".into())]);
+    assert_eq!(diag.len(), 0);
+    Lexer::new(source, FileId::default(), &mut diag).for_each(drop);
+    assert_eq!(diag.len(), 1);
+}
+
+#[test]
+fn adapters() {
+    let mut diag = Diagnostics::new();
+    let lexer = Lexer::new(b"f(a, b, c);", FileId::default(), &mut diag);
+    let commas = lexer.filter(|tok| tok.tokentype == TokenType::COMMA).count();
+    assert_eq!(commas, 2);
+}
+
+#[test]
+fn large_source() {
+    let source = "x = y + 1;\n".repeat(20_000);
+    let mut diag = Diagnostics::new();
+    let lexer = Lexer::new(source.as_bytes(), FileId::default(), &mut diag);
+    let mut previous = 0;
+    let mut count = 0;
+    for tok in lexer {
+        assert!(tok.location.offset >= previous);
+        previous = tok.location.offset;
+        count += 1;
+    }
+    assert_eq!(count, 6 * 20_000 + 1);
+}
@@ -0,0 +1,57 @@
+//! # Trace
+//! `-ftrace-macros` prints every expansion before it is
+//! rescanned, and diagnostics coming from an expansion are
+//! followed by the chain of macros they were expanded from.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::process::Command;
+
+use common::TempDir;
+
+static SOURCE: &str = "#define MAX(a, b) ((a) > (b) ? (a) : (b))
+#define TWICE(x) MAX(x, x)
+#define P(a, b) a ## b
+#define Q P(.., .)
+TWICE(1)
+Q
+#define E
+E
+";
+
+/// Preprocesses `SOURCE` as `t.c` with `args`, returning
+/// what is printed on stdout and stderr.
+fn run(name: &str, args: &[&str]) -> (String, String) {
+    let dir = TempDir::new(name, &[("t.c", SOURCE)]);
+    let output = Command::new(env!("CARGO_BIN_EXE_tornado-cc"))
+        .args(["-E", "-P"])
+        .args(args)
+        .arg("t.c")
+        .current_dir(&dir.path)
+        .output()
+        .unwrap();
+    return (String::from_utf8_lossy(&output.stdout).into_owned(), String::from_utf8_lossy(&output.stderr).into_owned());
+}
+
+#[test]
+fn trace_macros() {
+    let (stdout, stderr) = run("trace-macros", &["-ftrace-macros"]);
+    assert_eq!(stdout, "((1) > (1) ? (1) : (1))\n.. .\n");
+    assert_eq!(stderr.lines().take(5).collect::<Vec<_>>(), [
+        "t.c:5: TWICE(1) -> MAX(1, 1)",
+        "t.c:5:   MAX(1, 1) -> ((1) > (1) ? (1) : (1))",
+        "t.c:6: Q -> P(.., .)",
+        "t.c:6:   P(.., .) -> .. .",
+        "t.c:8: E -> ",
+    ]);
+}
+
+#[test]
+fn expanded_from() {
+    let (_, stderr) = run("expanded-from", &[]);
+    assert_eq!(stderr, "t.c:6:1: error: pasting does not give a valid preprocessing token\n\
+        t.c:3:9: note: expanded from macro 'P'\n\
+        t.c:4:9: note: expanded from macro 'Q'\n");
+}
@@ -0,0 +1,73 @@
+//! # Trigraphs
+//! Without `-trigraphs`, trigraphs are left as they are
+//! and warned about, except in comments, where only a
+//! `??/` at the end of a line is, since it would have
+//! continued a `//` comment. The spelling of the tokens
+//! keeps them as they are too.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::front::lex::Lexer;
+use tornado_cc::util::diag::{CompilerDiagnostic, Diagnostics};
+use tornado_cc::util::diag::warn::CompilerWarning;
+use tornado_cc::util::scan::source::FileId;
+
+static IGNORED: &[(&str, &[char])] = &[
+    ("a ??= b", &['=']),
+    ("a ??) ??( b", &[')', '(']),
+    ("\"what??!\"", &['!']),
+    ("// c ??/\nint x;", &['/']),
+    ("// c ??/\r\nint x;", &['/']),
+    ("// c ??/ x\nint x;", &[]),
+    ("// c ??= ??! ??(\nint x;", &[]),
+    ("/* c ??= */ int x;", &[]),
+    ("a ? ?= b", &[]),
+];
+
+// The source, and its output without and with `-trigraphs`
+static SPELLED: &[(&str, &str, &str)] = &[
+    ("\"??/\"", "\"??/\"", "\"\\\""),
+    ("\"??/\"\"", "\"??/\"\"", "\"\\\"\""),
+    ("'??''", "'??''", "'^'"),
+    ("\"??=??(??)\"", "\"??=??(??)\"", "\"#[]\""),
+];
+
+/// Lexes `source` without trigraphs, returning the
+/// trigraphs that were warned about.
+fn ignored(source: &str) -> Vec<char> {
+    let mut diag = Diagnostics::new();
+    Lexer::new(source.as_bytes(), FileId::default(), &mut diag).for_each(drop);
+    return diag.iter().map(|diagnostic| match diagnostic.diagnostic {
+        CompilerDiagnostic::Warning(CompilerWarning::TRIGRAPH_IGNORED(c)) => c,
+        _ => panic!("{source:?} has unexpected diagnostics"),
+    }).collect();
+}
+
+#[test]
+fn ignored_trigraphs() {
+    for (source, expected) in IGNORED {
+        assert_eq!(ignored(source), *expected, "{source:?}");
+    }
+}
+
+#[test]
+fn spelled_as_written() {
+    for (source, without, with) in SPELLED {
+        let (text, _) = common::preprocess(source, &Options::default());
+        assert_eq!(text, *without, "{source:?} without trigraphs");
+        let options = Options { trigraphs: true, ..Options::default() };
+        assert_eq!(common::preprocess(source, &options), (with.to_string(), vec![]), "{source:?} with trigraphs");
+    }
+}
+
+#[test]
+fn stringized_as_written() {
+    let source = "#define S(x) #x\nS(\"??/\")";
+    assert_eq!(common::preprocess(source, &Options::default()).0, "\"\\\"??/\\\"\"");
+    let source = "#define S(x) #x\nS(\"??/\"\")";
+    let options = Options { trigraphs: true, ..Options::default() };
+    assert_eq!(common::preprocess(source, &options), ("\"\\\"\\\\\\\"\\\"\"".to_string(), vec![]));
+}
@@ -0,0 +1,59 @@
+//! # Trivia
+//! With trivia, the tokens keep everything around them, so
+//! that the source can be rebuilt from them byte for byte,
+//! whatever it is made of.
+
+#![allow(clippy::needless_return)]
+
+use tornado_cc::front::lex::{self, Lexer, TokenType};
+use tornado_cc::util::diag::Diagnostics;
+use tornado_cc::util::scan::source::FileId;
+
+static SOURCES: &[&[u8]] = &[
+    b"",
+    b"int main(void) { return 0; }\n",
+    b"  \t\n\n  x  \n",
+    b"/* block */ a // line\nb /* multi\nline */ c",
+    b"in\\\nt x = 1\\\r\n2;",
+    b"a\r\nb\rc\n",
+    b"\xEF\xBB\xBFint bom;\n",
+    b"#include <stdio.h>\n#define F(x) x \\\n  + 1\nF(2)\n",
+    b"  # if 1 // comment\nyes\n#endif",
+    b"\"unterminated\nchar c = 'x;\n\"ok\"",
+    b"L\"wide\" u8\"utf\" U'c' .5e+3f 0x1p-2 1'000",
+    b"a ??= b ??/\nc",
+    b"<: :> <% %> %: %:%:",
+    b"@ ` \\ $",
+    b"/* unterminated comment",
+    b"x // trailing splice \\\n still a comment\ny",
+    b"caf\xC3\xA9 \\u00E9 \xFF\xFE",
+    b"no newline at the end",
+];
+
+/// Lexes `source` with trivia, and rebuilds it from the tokens.
+fn round_trip(source: &[u8], trigraphs: bool) -> Option<Vec<u8>> {
+    let mut diag = Diagnostics::new();
+    let mut lexer = Lexer::new(source, FileId::default(), &mut diag);
+    lexer.set_trivia(true);
+    lexer.set_trigraphs(trigraphs);
+    let tokens: Vec<_> = lexer.collect();
+    assert!(tokens.last().is_some_and(|tok| tok.tokentype == TokenType::EOF));
+    return lex::reconstruct(&tokens);
+}
+
+#[test]
+fn byte_for_byte() {
+    for source in SOURCES {
+        for trigraphs in [false, true] {
+            let rebuilt = round_trip(source, trigraphs);
+            assert_eq!(rebuilt.as_deref(), Some(*source), "{:?}", String::from_utf8_lossy(source));
+        }
+    }
+}
+
+#[test]
+fn without_trivia() {
+    let mut diag = Diagnostics::new();
+    let tokens: Vec<_> = Lexer::new(b"int x;", FileId::default(), &mut diag).collect();
+    assert_eq!(lex::reconstruct(&tokens), None);
+}
@@ -0,0 +1,98 @@
+//! # Variadic
+//! Variadic macros take their extra arguments as `__VA_ARGS__`,
+//! or a name with GNU's `args...`. `, ## __VA_ARGS__` drops the
+//! comma when there are none, and C23's `__VA_OPT__(tokens)`
+//! keeps its tokens only when there are some.
+
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::Options;
+use tornado_cc::util::lang::Standard;
+
+static VARIADIC: &[(&str, &str)] = &[
+    ("#define V(...) [__VA_ARGS__]\nV()", "[]"),
+    ("#define V(...) [__VA_ARGS__]\nV(1, 2 ,3)", "[1, 2 ,3]"),
+    ("#define V(a, ...) a:__VA_ARGS__\nV(1, )", "1:"),
+    ("#define V(fmt, ...) f(fmt, ## __VA_ARGS__)\nV(a) V(a, b)", "f(a) f(a, b)"),
+    ("#define V(args...) [args]\nV(1, 2)", "[1, 2]"),
+    ("#define V(...) #__VA_ARGS__\nV( a , b )", "\"a , b\""),
+];
+
+// The examples of C23 6.10.5.2
+static VA_OPT: &[(&str, &str)] = &[
+    ("#define F(...) f(0 __VA_OPT__(,) __VA_ARGS__)\n#define EMP\nF(a,b,c) F() F(EMP)",
+        "f(0 , a,b,c) f(0) f(0)"),
+    ("#define G(X, ...) f(0, X __VA_OPT__(,) __VA_ARGS__)\nG(a,b,c) G(a,) G(a)",
+        "f(0, a , b,c) f(0, a) f(0, a)"),
+    ("#define SDEF(sname, ...) S sname __VA_OPT__(= { __VA_ARGS__ })\nSDEF(foo); SDEF(bar, 1, 2);",
+        "S foo; S bar = { 1, 2 };"),
+    ("#define H2(X, Y, ...) __VA_OPT__(X ## Y,) __VA_ARGS__\nH2(a, b, c, d)", "ab, c, d"),
+    ("#define H3(X, ...) #__VA_OPT__(X##X X##X)\nH3(, 0)", "\"\""),
+    ("#define H4(X, ...) __VA_OPT__(a X ## X) ## b\nH4(, 1)", "a b"),
+    ("#define H5A(...) __VA_OPT__()/**/__VA_OPT__()\n#define H5B(X) a ## X ## b\n\
+        #define H5C(X) H5B(X)\nH5C(H5A())", "ab"),
+];
+
+static MALFORMED: &[(&str, &str)] = &[
+    ("#define V(..., x) x\n", "error: malformed macro parameter list"),
+    ("#define V(__VA_ARGS__) 1\n", "error: malformed macro parameter list"),
+    ("#define V(...) __VA_OPT__\n", "error: __VA_OPT__ must be followed by a parenthesized list of tokens"),
+    ("#define V(...) __VA_OPT__(a\n", "error: __VA_OPT__ must be followed by a parenthesized list of tokens"),
+    ("#define V(...) __VA_OPT__(## a)\n", "error: '##' cannot appear at either end of a macro expansion"),
+];
+
+// Example 7 of C99 6.10.3.5
+static EXAMPLE_7: &str = "#define debug(...) fprintf(stderr, __VA_ARGS__)
+#define showlist(...) puts(#__VA_ARGS__)
+#define report(test, ...) ((test)?puts(#test):\\
+ printf(__VA_ARGS__))
+debug(\"Flag\");
+debug(\"X = %d\\n\", x);
+showlist(The first, second, and third items.);
+report(x>y, \"x is %d but y is %d\", x, y);
+";
+
+fn preprocess(source: &str) -> (String, Vec<String>) {
+    return common::preprocess(source, &Options::standard(Standard::C23));
+}
+
+fn check(cases: &[(&str, &str)]) {
+    for (source, expected) in cases {
+        assert_eq!(preprocess(source), (expected.to_string(), vec![]), "{source:?}");
+    }
+}
+
+#[test]
+fn variadic() {
+    check(VARIADIC);
+}
+
+#[test]
+fn va_opt() {
+    check(VA_OPT);
+}
+
+#[test]
+fn malformed() {
+    for (source, expected) in MALFORMED {
+        let (_, diagnostics) = preprocess(source);
+        assert_eq!(diagnostics, [*expected], "{source:?}");
+    }
+}
+
+#[test]
+fn missing_variadic_arguments() {
+    // Allowed by C23, and by GNU before it
+    assert_eq!(preprocess("#define V(a, ...) a __VA_ARGS__\nV(1)"), ("1".to_string(), vec![]));
+}
+
+#[test]
+fn example_7() {
+    let expected = "fprintf(stderr, \"Flag\");\n\
+        fprintf(stderr, \"X = %d\\n\", x);\n\
+        puts(\"The first, second, and third items.\");\n\
+        ((x>y)?puts(\"x>y\"): printf(\"x is %d but y is %d\", x, y));";
+    assert_eq!(preprocess(EXAMPLE_7), (expected.to_string(), vec![]));
+}